@@ -0,0 +1,501 @@
+//! Typed addresses of the X Air mixers' parameters.
+
+use core::fmt;
+use core::str::FromStr;
+
+#[cfg(feature = "alloc")]
+use alloc::string::ToString;
+
+#[cfg(feature = "alloc")]
+use argument::Argument;
+#[cfg(feature = "alloc")]
+use command::Command;
+use error::AddressError;
+#[cfg(feature = "std")]
+use info::Model;
+
+/// The most input channels any X Air mixer has.
+pub const MAX_CHANNELS: u8 = 16;
+/// The most mix buses any X Air mixer has.
+pub const MAX_BUSES: u8 = 6;
+/// The number of effects returns on every X Air mixer.
+pub const FX_RETURNS: u8 = 4;
+/// The number of DCA groups on every X Air mixer.
+pub const DCAS: u8 = 4;
+
+/// A channel strip, bus or group on an X Air mixer. Numbers start at 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Strip {
+    /// An input channel, `/ch/01` to `/ch/16`.
+    Channel(u8),
+    /// The stereo aux input, `/rtn/aux`.
+    AuxIn,
+    /// An effects return, `/rtn/1` to `/rtn/4`.
+    FxReturn(u8),
+    /// A mix bus, `/bus/1` to `/bus/6`.
+    Bus(u8),
+    /// The main left/right mix, `/lr`.
+    Main,
+    /// A DCA group, `/dca/1` to `/dca/4`.
+    Dca(u8),
+}
+
+impl Strip {
+
+    /// Checks that the strip's number is in range on at least one X Air mixer.
+    pub fn validate(self) -> Result<Strip, AddressError> {
+        let (kind, number, max) = match self {
+            Strip::Channel(n) => ("channel", n, MAX_CHANNELS),
+            Strip::FxReturn(n) => ("effects return", n, FX_RETURNS),
+            Strip::Bus(n) => ("bus", n, MAX_BUSES),
+            Strip::Dca(n) => ("DCA", n, DCAS),
+            Strip::AuxIn | Strip::Main => return Ok(self),
+        };
+        if number < 1 || number > max {
+            return Err(AddressError::OutOfRange { kind, number, min: 1, max });
+        }
+        Ok(self)
+    }
+
+    /// Checks that `model` has the strip.
+    ///
+    /// The XR12 has 12 channels and 2 buses, the XR16 16 channels and 4 buses, and the XR18 16
+    /// channels and 6 buses. The X32 family has a different address space, so fails with
+    /// `AddressError::UnsupportedModel`.
+    #[cfg(feature = "std")]
+    pub fn check(self, model: Model) -> Result<(), AddressError> {
+        let (channels, buses) = match model {
+            Model::Xr12 => (12, 2),
+            Model::Xr16 => (16, 4),
+            Model::Xr18 => (16, 6),
+            _ => return Err(AddressError::UnsupportedModel),
+        };
+        match self.validate()? {
+            Strip::Channel(number) if number > channels => {
+                Err(AddressError::OutOfRange { kind: "channel", number, min: 1, max: channels })
+            },
+            Strip::Bus(number) if number > buses => {
+                Err(AddressError::OutOfRange { kind: "bus", number, min: 1, max: buses })
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the number of bands in the strip's parametric EQ: 4 on a channel, 6 on a bus
+    /// or the main mix, and 0 on strips without one.
+    pub fn eq_bands(self) -> u8 {
+        match self {
+            Strip::Channel(_) => 4,
+            Strip::Bus(_) | Strip::Main => 6,
+            Strip::AuxIn | Strip::FxReturn(_) | Strip::Dca(_) => 0,
+        }
+    }
+}
+
+impl fmt::Display for Strip {
+    /// Writes the address prefix of the strip, such as `/ch/01`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Strip::Channel(n) => write!(f, "/ch/{:02}", n),
+            Strip::AuxIn => f.write_str("/rtn/aux"),
+            Strip::FxReturn(n) => write!(f, "/rtn/{}", n),
+            Strip::Bus(n) => write!(f, "/bus/{}", n),
+            Strip::Main => f.write_str("/lr"),
+            Strip::Dca(n) => write!(f, "/dca/{}", n),
+        }
+    }
+}
+
+/// A setting of a strip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Parameter {
+    /// The fader level, a float from 0.0 to 1.0.
+    Fader,
+    /// Whether the strip is on, the integer 1, or muted, 0.
+    On,
+    /// The pan, a float from 0.0 (left) to 1.0 (right). DCA groups have none.
+    Pan,
+    /// The strip's name, a string.
+    Name,
+    /// The color of the strip's scribble strip, an integer from 0 to 15.
+    Color,
+    /// The gain of a channel's head amp, a float from 0.0 (-12 dB) to 1.0 (+60 dB). Sent to
+    /// `/headamp/NN/gain`, for the head amp with the channel's number.
+    Gain,
+    /// Whether a channel's head amp supplies phantom power, the integer 1, or not, 0. Sent to
+    /// `/headamp/NN/phantom`.
+    Phantom,
+    /// Whether a channel's low cut filter is on, the integer 1, or off, 0.
+    LowCut,
+    /// The frequency of a channel's low cut filter, a float from 0.0 (20 Hz) to 1.0 (400 Hz)
+    /// on a log scale.
+    LowCutFrequency,
+    /// Whether a channel's polarity is inverted, the integer 1, or not, 0.
+    Invert,
+    /// Whether the strip's EQ is on, the integer 1, or bypassed, 0.
+    EqOn,
+    /// The type of an EQ band, an integer from 0 to 5. Bands are numbered from 1.
+    EqType(u8),
+    /// The frequency of an EQ band, a float from 0.0 (20 Hz) to 1.0 (20 kHz) on a log scale.
+    EqFrequency(u8),
+    /// The gain of an EQ band, a float from 0.0 (-15 dB) to 1.0 (+15 dB).
+    EqGain(u8),
+    /// The Q of an EQ band, a float from 0.0 (10) to 1.0 (0.3) on a log scale.
+    EqQ(u8),
+}
+
+/// Every parameter that does not belong to an EQ band, in the order they are tried when
+/// parsing.
+const PARAMETERS: [Parameter; 11] = [
+    Parameter::Fader,
+    Parameter::On,
+    Parameter::Pan,
+    Parameter::Name,
+    Parameter::Color,
+    Parameter::Gain,
+    Parameter::Phantom,
+    Parameter::LowCut,
+    Parameter::LowCutFrequency,
+    Parameter::Invert,
+    Parameter::EqOn,
+];
+
+impl Parameter {
+
+    /// Returns the parameter's address below the strip, the channel's head amp or the EQ
+    /// band, or `None` if the strip does not have it.
+    fn path(self, strip: Strip) -> Option<&'static str> {
+        let dca = matches!(strip, Strip::Dca(_));
+        let channel = matches!(strip, Strip::Channel(_));
+        if let Some(band) = self.band() {
+            if band < 1 || band > strip.eq_bands() {
+                return None;
+            }
+        }
+        match self {
+            Parameter::EqOn if strip.eq_bands() > 0 => Some("eq/on"),
+            Parameter::EqType(_) => Some("type"),
+            Parameter::EqFrequency(_) => Some("f"),
+            Parameter::EqGain(_) => Some("g"),
+            Parameter::EqQ(_) => Some("q"),
+            Parameter::EqOn => None,
+            Parameter::Fader if dca => Some("fader"),
+            Parameter::On if dca => Some("on"),
+            Parameter::Pan if dca => None,
+            Parameter::Fader => Some("mix/fader"),
+            Parameter::On => Some("mix/on"),
+            Parameter::Pan => Some("mix/pan"),
+            Parameter::Name => Some("config/name"),
+            Parameter::Color => Some("config/color"),
+            _ if !channel => None,
+            Parameter::Gain => Some("gain"),
+            Parameter::Phantom => Some("phantom"),
+            Parameter::LowCut => Some("preamp/hpon"),
+            Parameter::LowCutFrequency => Some("preamp/hpf"),
+            Parameter::Invert => Some("preamp/invert"),
+        }
+    }
+
+    /// Returns the number of the EQ band the parameter belongs to, if it belongs to one.
+    pub fn band(self) -> Option<u8> {
+        match self {
+            Parameter::EqType(band) |
+            Parameter::EqFrequency(band) |
+            Parameter::EqGain(band) |
+            Parameter::EqQ(band) => Some(band),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the parameter belongs to the channel's head amp rather than the
+    /// channel itself.
+    fn on_headamp(self) -> bool {
+        matches!(self, Parameter::Gain | Parameter::Phantom)
+    }
+}
+
+/// The address of a parameter on an X Air mixer, such as `/ch/01/mix/fader`.
+///
+/// Addresses are printed with `Display` and parsed with `FromStr`, which accepts exactly the
+/// addresses the mixer uses, so `/ch/1/mix/fader` is an error.
+///
+/// ```
+/// use april_2018_challenge::{Address, Command, Model, Parameter, Strip};
+///
+/// let fader = Address::new(Strip::Channel(1), Parameter::Fader).unwrap();
+/// assert_eq!(fader.to_string(), "/ch/01/mix/fader");
+/// assert_eq!(fader.set(0.75), Command::new("/ch/01/mix/fader").arg(0.75));
+///
+/// let mute: Address = "/rtn/aux/mix/on".parse().unwrap();
+/// assert_eq!(mute, Address::new(Strip::AuxIn, Parameter::On).unwrap());
+/// assert!("/ch/1/mix/fader".parse::<Address>().is_err());
+///
+/// let eq: Address = "/lr/eq/6/f".parse().unwrap();
+/// assert_eq!(eq, Address::new(Strip::Main, Parameter::EqFrequency(6)).unwrap());
+/// assert!(Address::new(Strip::Channel(1), Parameter::EqFrequency(5)).is_err());
+///
+/// let bus = Address::new(Strip::Bus(6), Parameter::Fader).unwrap();
+/// assert!(bus.check(Model::Xr18).is_ok());
+/// assert!(bus.check(Model::Xr12).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Address {
+    strip: Strip,
+    parameter: Parameter,
+}
+
+impl Address {
+
+    /// Creates the address of a strip's parameter, checking that the strip's number is in
+    /// range on at least one X Air mixer and that the strip has the parameter, including the
+    /// EQ band.
+    pub fn new(strip: Strip, parameter: Parameter) -> Result<Address, AddressError> {
+        let strip = strip.validate()?;
+        if let Some(number) = parameter.band() {
+            let max = strip.eq_bands();
+            if max > 0 && (number < 1 || number > max) {
+                return Err(AddressError::OutOfRange { kind: "EQ band", number, min: 1, max });
+            }
+        }
+        if parameter.path(strip).is_none() {
+            return Err(AddressError::NoSuchParameter);
+        }
+        Ok(Address { strip, parameter })
+    }
+
+    /// Returns the strip the parameter belongs to.
+    pub fn strip(&self) -> Strip {
+        self.strip
+    }
+
+    /// Returns the parameter.
+    pub fn parameter(&self) -> Parameter {
+        self.parameter
+    }
+
+    /// Checks that `model` has the strip. See [`Strip::check`](enum.Strip.html#method.check).
+    #[cfg(feature = "std")]
+    pub fn check(&self, model: Model) -> Result<(), AddressError> {
+        self.strip.check(model)
+    }
+
+    /// Returns the command that asks the mixer for the parameter's value.
+    #[cfg(feature = "alloc")]
+    pub fn get(&self) -> Command {
+        Command::new(self.to_string())
+    }
+
+    /// Returns the command that sets the parameter to `value`.
+    #[cfg(feature = "alloc")]
+    pub fn set<A: Into<Argument>>(&self, value: A) -> Command {
+        Command::new(self.to_string()).arg(value)
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // `new` only creates addresses whose strip has the parameter.
+        let path = self.parameter.path(self.strip).unwrap_or("");
+        match (self.strip, self.parameter.band()) {
+            (Strip::Channel(n), _) if self.parameter.on_headamp() => {
+                write!(f, "/headamp/{:02}/{}", n, path)
+            },
+            (strip, Some(band)) => write!(f, "{}/eq/{}/{}", strip, band, path),
+            (strip, None) => write!(f, "{}/{}", strip, path),
+        }
+    }
+}
+
+impl FromStr for Address {
+    type Err = AddressError;
+
+    fn from_str(s: &str) -> Result<Address, AddressError> {
+        let (strip, path, headamp) = parse_strip(s)?;
+        let strip = strip.validate()?;
+        if let Some(parameter) = parse_eq_band(path).filter(|_| !headamp) {
+            return Address::new(strip, parameter).map_err(|e| match e {
+                AddressError::NoSuchParameter => AddressError::Unknown,
+                e => e,
+            });
+        }
+        PARAMETERS.iter()
+            .cloned()
+            .find(|parameter| {
+                parameter.on_headamp() == headamp && parameter.path(strip) == Some(path)
+            })
+            .map(|parameter| Address { strip, parameter })
+            .ok_or(AddressError::Unknown)
+    }
+}
+
+/// Splits an address into its strip and the path of the parameter below it, and whether the
+/// path is below the channel's head amp.
+fn parse_strip(s: &str) -> Result<(Strip, &str, bool), AddressError> {
+    if let Some(path) = s.strip_prefix("/lr/") {
+        return Ok((Strip::Main, path, false));
+    }
+    if let Some(path) = s.strip_prefix("/rtn/aux/") {
+        return Ok((Strip::AuxIn, path, false));
+    }
+
+    let mut parts = s.splitn(4, '/');
+    let (kind, number, path) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(""), Some(kind), Some(number), Some(path)) => (kind, number, path),
+        _ => return Err(AddressError::Unknown),
+    };
+    // Channel and head amp numbers are always two digits, and the others always one.
+    let strip = match (kind, number.len()) {
+        ("ch", 2) | ("headamp", 2) => Strip::Channel(parse_number(number)?),
+        ("rtn", 1) => Strip::FxReturn(parse_number(number)?),
+        ("bus", 1) => Strip::Bus(parse_number(number)?),
+        ("dca", 1) => Strip::Dca(parse_number(number)?),
+        _ => return Err(AddressError::Unknown),
+    };
+    Ok((strip, path, kind == "headamp"))
+}
+
+/// Parses the path of an EQ band's parameter below its strip, such as `eq/1/f`.
+fn parse_eq_band(path: &str) -> Option<Parameter> {
+    let mut parts = path.splitn(3, '/');
+    let (band, name) = match (parts.next(), parts.next(), parts.next()) {
+        (Some("eq"), Some(band), Some(name)) if band.len() == 1 => (band, name),
+        _ => return None,
+    };
+    let band = parse_number(band).ok()?;
+    match name {
+        "type" => Some(Parameter::EqType(band)),
+        "f" => Some(Parameter::EqFrequency(band)),
+        "g" => Some(Parameter::EqGain(band)),
+        "q" => Some(Parameter::EqQ(band)),
+        _ => None,
+    }
+}
+
+/// Parses a strip number made only of digits.
+fn parse_number(s: &str) -> Result<u8, AddressError> {
+    if !s.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(AddressError::Unknown);
+    }
+    s.parse().map_err(|_| AddressError::Unknown)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "alloc")]
+    use alloc::string::ToString;
+
+    /// Addresses of every kind, and the strip and parameter each names.
+    const ADDRESSES: [(&str, Strip, Parameter); 16] = [
+        ("/headamp/01/gain", Strip::Channel(1), Parameter::Gain),
+        ("/headamp/16/phantom", Strip::Channel(16), Parameter::Phantom),
+        ("/ch/05/preamp/hpf", Strip::Channel(5), Parameter::LowCutFrequency),
+        ("/ch/12/eq/on", Strip::Channel(12), Parameter::EqOn),
+        ("/ch/01/eq/1/type", Strip::Channel(1), Parameter::EqType(1)),
+        ("/ch/10/eq/4/f", Strip::Channel(10), Parameter::EqFrequency(4)),
+        ("/bus/6/eq/6/g", Strip::Bus(6), Parameter::EqGain(6)),
+        ("/lr/eq/3/q", Strip::Main, Parameter::EqQ(3)),
+        ("/dca/1/fader", Strip::Dca(1), Parameter::Fader),
+        ("/dca/4/on", Strip::Dca(4), Parameter::On),
+        ("/dca/2/config/name", Strip::Dca(2), Parameter::Name),
+        ("/rtn/1/mix/fader", Strip::FxReturn(1), Parameter::Fader),
+        ("/rtn/4/config/color", Strip::FxReturn(4), Parameter::Color),
+        ("/rtn/aux/mix/pan", Strip::AuxIn, Parameter::Pan),
+        ("/rtn/aux/mix/on", Strip::AuxIn, Parameter::On),
+        ("/bus/1/mix/fader", Strip::Bus(1), Parameter::Fader),
+    ];
+
+    #[test]
+    fn parses_every_kind_of_address() {
+        for &(s, strip, parameter) in &ADDRESSES {
+            let address = Address::new(strip, parameter).unwrap();
+            assert_eq!(s.parse(), Ok(address), "{}", s);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn prints_every_kind_of_address() {
+        for &(s, strip, parameter) in &ADDRESSES {
+            assert_eq!(Address::new(strip, parameter).unwrap().to_string(), s);
+        }
+    }
+
+    #[test]
+    fn rejects_numbers_that_are_not_zero_padded() {
+        for &s in &["/ch/1/mix/fader", "/ch/001/mix/fader", "/headamp/1/gain",
+                    "/headamp/001/gain", "/bus/01/mix/fader", "/dca/01/fader",
+                    "/rtn/01/mix/fader", "/ch/01/eq/01/f", "/ch/+1/mix/fader", "/ch/ 1/mix/on"] {
+            assert_eq!(s.parse::<Address>(), Err(AddressError::Unknown), "{}", s);
+        }
+    }
+
+    #[test]
+    fn rejects_parameters_the_strip_does_not_have() {
+        for &s in &["/ch/01/gain", "/headamp/01/mix/fader", "/dca/1/mix/fader", "/dca/1/pan",
+                    "/rtn/1/eq/1/f", "/rtn/aux/eq/on", "/bus/1/preamp/hpf", "/lr/eq/1/x",
+                    "/dca/1/eq/on"] {
+            assert_eq!(s.parse::<Address>(), Err(AddressError::Unknown), "{}", s);
+        }
+        assert_eq!(Address::new(Strip::Dca(1), Parameter::Pan),
+                   Err(AddressError::NoSuchParameter));
+        assert_eq!(Address::new(Strip::Bus(1), Parameter::Gain),
+                   Err(AddressError::NoSuchParameter));
+        assert_eq!(Address::new(Strip::FxReturn(1), Parameter::EqGain(1)),
+                   Err(AddressError::NoSuchParameter));
+    }
+
+    #[test]
+    fn rejects_numbers_out_of_range() {
+        let out_of_range = |kind, number, max| {
+            Err(AddressError::OutOfRange { kind, number, min: 1, max })
+        };
+        assert_eq!("/ch/00/mix/fader".parse::<Address>(), out_of_range("channel", 0, 16));
+        assert_eq!("/ch/17/mix/fader".parse::<Address>(), out_of_range("channel", 17, 16));
+        assert_eq!("/headamp/17/gain".parse::<Address>(), out_of_range("channel", 17, 16));
+        assert_eq!("/bus/7/mix/fader".parse::<Address>(), out_of_range("bus", 7, 6));
+        assert_eq!("/rtn/5/mix/fader".parse::<Address>(),
+                   out_of_range("effects return", 5, 4));
+        assert_eq!("/dca/5/fader".parse::<Address>(), out_of_range("DCA", 5, 4));
+        assert_eq!("/dca/0/fader".parse::<Address>(), out_of_range("DCA", 0, 4));
+        assert_eq!("/ch/01/eq/5/f".parse::<Address>(), out_of_range("EQ band", 5, 4));
+        assert_eq!("/ch/01/eq/0/f".parse::<Address>(), out_of_range("EQ band", 0, 4));
+        assert_eq!("/bus/1/eq/7/g".parse::<Address>(), out_of_range("EQ band", 7, 6));
+        assert_eq!(Address::new(Strip::Main, Parameter::EqQ(7)), out_of_range("EQ band", 7, 6));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn checks_strips_against_each_model() {
+        let out_of_range = |kind, number, max| {
+            Err(AddressError::OutOfRange { kind, number, min: 1, max })
+        };
+        for &(model, channels, buses) in &[(Model::Xr12, 12, 2), (Model::Xr16, 16, 4),
+                                           (Model::Xr18, 16, 6)] {
+            assert_eq!(Strip::Channel(channels).check(model), Ok(()));
+            assert_eq!(Strip::Bus(buses).check(model), Ok(()));
+            if channels < MAX_CHANNELS {
+                assert_eq!(Strip::Channel(channels + 1).check(model),
+                           out_of_range("channel", channels + 1, channels));
+            }
+            if buses < MAX_BUSES {
+                assert_eq!(Strip::Bus(buses + 1).check(model),
+                           out_of_range("bus", buses + 1, buses));
+            }
+            // No model has more than any X Air mixer.
+            assert_eq!(Strip::Channel(17).check(model), out_of_range("channel", 17, 16));
+            assert_eq!(Strip::Bus(7).check(model), out_of_range("bus", 7, 6));
+            for &strip in &[Strip::AuxIn, Strip::FxReturn(4), Strip::Main, Strip::Dca(4)] {
+                assert_eq!(strip.check(model), Ok(()));
+            }
+        }
+
+        let address: Address = "/ch/13/mix/fader".parse().unwrap();
+        assert_eq!(address.check(Model::Xr12), out_of_range("channel", 13, 12));
+        assert_eq!(address.check(Model::Xr16), Ok(()));
+        for &model in &[Model::X32, Model::X32Compact, Model::X32Producer, Model::X32Rack,
+                        Model::X32Core] {
+            assert_eq!(address.check(model), Err(AddressError::UnsupportedModel));
+        }
+    }
+}
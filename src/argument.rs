@@ -0,0 +1,619 @@
+//! OSC command arguments.
+
+use core::char;
+#[cfg(feature = "alloc")]
+use core::convert::Infallible;
+#[cfg(feature = "alloc")]
+use core::slice;
+use core::str;
+
+#[cfg(feature = "alloc")]
+use alloc::string::{String, ToString};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use byteorder::{BigEndian, ByteOrder};
+
+use {decode_string, split, unpad, MAX_ARRAY_DEPTH};
+#[cfg(feature = "alloc")]
+use encode::{self, padded_len, put_padding, put_string, Output};
+use error::DecodeError;
+use timetag::Timetag;
+
+/// An OSC Command argument.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Argument {
+    /// `s`: a string.
+    String(String),
+    /// `i`: a 32-bit integer.
+    Integer(i32),
+    /// `f`: a 32-bit float.
+    Float(f32),
+    /// `b`: a blob of binary data.
+    Binary(Vec<u8>),
+    /// `h`: a 64-bit integer.
+    Long(i64),
+    /// `d`: a 64-bit float.
+    Double(f64),
+    /// `t`: a time tag.
+    Timetag(Timetag),
+    /// `S`: a symbol, an alternate string type for systems that distinguish the two.
+    Symbol(String),
+    /// `c`: a character.
+    Char(char),
+    /// `r`: a 32-bit RGBA color.
+    Color(Rgba),
+    /// `m`: a 4-byte MIDI message.
+    Midi(Midi),
+    /// `T` or `F`: a boolean, carried entirely in the type tag.
+    Bool(bool),
+    /// `N`: nil.
+    Nil,
+    /// `I`: infinitum, also known as impulse or bang.
+    Infinitum,
+    /// `[` ... `]`: an array of arguments.
+    Array(Vec<Argument>),
+}
+
+/// An RGBA color, as carried by the `r` type tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rgba {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+    pub alpha: u8,
+}
+
+/// A MIDI message, as carried by the `m` type tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Midi {
+    pub port: u8,
+    pub status: u8,
+    pub data1: u8,
+    pub data2: u8,
+}
+
+#[cfg(feature = "alloc")]
+impl Argument {
+
+    /// Encode the argument to a buffer, including any padding needed to keep the buffer 4-byte
+    /// aligned.
+    ///
+    /// Arrays are encoded however deeply they are nested, though the decoders reject arrays
+    /// nested more than [`MAX_ARRAY_DEPTH`](constant.MAX_ARRAY_DEPTH.html) deep.
+    pub fn encode(&self, buf: &mut Vec<u8>) {
+        encode::to_vec(buf, |out| self.put(out))
+    }
+
+    /// Returns the number of bytes the encoded argument takes up, including padding.
+    pub fn encoded_len(&self) -> usize {
+        let mut len = 0;
+        let result: Result<(), Infallible> = self.walk(|argument| {
+            len += argument.map_or(0, Argument::value_len);
+            Ok(())
+        });
+        match result {
+            Ok(()) => len,
+            Err(e) => match e {},
+        }
+    }
+
+    /// Returns the number of bytes the argument's own value takes up, which is none for an
+    /// array.
+    fn value_len(&self) -> usize {
+        match self {
+            Argument::String(s) | Argument::Symbol(s) => padded_len(s.len() + 1),
+            Argument::Binary(b) => 4 + padded_len(b.len()),
+            Argument::Integer(_) |
+            Argument::Float(_) |
+            Argument::Char(_) |
+            Argument::Color(_) |
+            Argument::Midi(_) => 4,
+            Argument::Long(_) | Argument::Double(_) | Argument::Timetag(_) => 8,
+            Argument::Bool(_) | Argument::Nil | Argument::Infinitum | Argument::Array(_) => 0,
+        }
+    }
+
+    pub(crate) fn put<O: Output>(&self, out: &mut O) -> Result<(), O::Error> {
+        self.walk(|argument| match argument {
+            Some(argument) => argument.put_value(out),
+            None => Ok(()),
+        })
+    }
+
+    /// Writes the argument's own value, which is nothing for an array.
+    fn put_value<O: Output>(&self, out: &mut O) -> Result<(), O::Error> {
+        let mut b = [0; 8];
+        match self {
+            Argument::String(s) | Argument::Symbol(s) => put_string(s, out),
+            Argument::Integer(i) => {
+                BigEndian::write_i32(&mut b, *i);
+                out.put(&b[..4])
+            },
+            Argument::Float(f) => {
+                BigEndian::write_f32(&mut b, *f);
+                out.put(&b[..4])
+            },
+            Argument::Binary(blob) => {
+                BigEndian::write_u32(&mut b, blob.len() as u32);
+                out.put(&b[..4])?;
+                out.put(blob)?;
+                put_padding(blob.len(), out)
+            },
+            Argument::Long(h) => {
+                BigEndian::write_i64(&mut b, *h);
+                out.put(&b)
+            },
+            Argument::Double(d) => {
+                BigEndian::write_f64(&mut b, *d);
+                out.put(&b)
+            },
+            Argument::Timetag(t) => {
+                BigEndian::write_u64(&mut b, t.to_bits());
+                out.put(&b)
+            },
+            Argument::Char(c) => {
+                BigEndian::write_u32(&mut b, *c as u32);
+                out.put(&b[..4])
+            },
+            Argument::Color(c) => out.put(&[c.red, c.green, c.blue, c.alpha]),
+            Argument::Midi(m) => out.put(&[m.port, m.status, m.data1, m.data2]),
+            Argument::Bool(_) | Argument::Nil | Argument::Infinitum | Argument::Array(_) => Ok(()),
+        }
+    }
+
+    /// Encodes the type tags for the argument to a buffer. Arrays encode the bracketed tags of
+    /// all their elements.
+    pub fn encode_tags(&self, buf: &mut Vec<u8>) {
+        encode::to_vec(buf, |out| self.put_tags(out))
+    }
+
+    /// Returns the number of type tags the argument needs. This is 1 except for arrays.
+    pub fn tags_len(&self) -> usize {
+        // One tag for each argument, counting `[` for an array, and a `]` to close each array.
+        let mut len = 0;
+        let result: Result<(), Infallible> = self.walk(|_| {
+            len += 1;
+            Ok(())
+        });
+        match result {
+            Ok(()) => len,
+            Err(e) => match e {},
+        }
+    }
+
+    pub(crate) fn put_tags<O: Output>(&self, out: &mut O) -> Result<(), O::Error> {
+        self.walk(|argument| match argument {
+            Some(argument) => out.put(&[argument.tag()]),
+            None => out.put(b"]"),
+        })
+    }
+
+    /// Calls `visit` with the argument and then, in order, with each element nested in it, and
+    /// with `None` after the last element of each array.
+    ///
+    /// The arrays still being visited are kept on a stack of its own rather than by recursion,
+    /// so that arrays nested any number of levels deep can be encoded.
+    fn walk<E, F>(&self, mut visit: F) -> Result<(), E>
+        where F: FnMut(Option<&Argument>) -> Result<(), E>
+    {
+        let mut arrays: Vec<slice::Iter<'_, Argument>> = Vec::new();
+        let mut next = Some(self);
+        loop {
+            if let Some(argument) = next {
+                visit(Some(argument))?;
+                if let Argument::Array(elements) = argument {
+                    arrays.push(elements.iter());
+                }
+            }
+            next = match arrays.last_mut() {
+                Some(elements) => elements.next(),
+                None => return Ok(()),
+            };
+            if next.is_none() {
+                arrays.pop();
+                visit(None)?;
+            }
+        }
+    }
+
+    /// Decodes an argument of the provided type from a buffer, returning the argument, and the
+    /// remaining buffer after any padding.
+    ///
+    /// Array elements take their type tags from `tags`, which must be positioned just after
+    /// `type_tag`.
+    pub fn decode<'a>(type_tag: char,
+                      tags: &mut str::Chars<'a>,
+                      buf: &'a [u8],
+                      strict: bool) -> Result<(Argument, &'a [u8]), DecodeError> {
+        let (argument, buf) = ArgumentRef::decode(type_tag, tags, buf, strict)?;
+        Ok((argument.to_owned(), buf))
+    }
+
+    /// Returns the tag byte for the argument type. For arrays this is the opening `[`.
+    pub fn tag(&self) -> u8 {
+        match self {
+            Argument::String(_) => b's',
+            Argument::Integer(_) => b'i',
+            Argument::Float(_) => b'f',
+            Argument::Binary(_) => b'b',
+            Argument::Long(_) => b'h',
+            Argument::Double(_) => b'd',
+            Argument::Timetag(_) => b't',
+            Argument::Symbol(_) => b'S',
+            Argument::Char(_) => b'c',
+            Argument::Color(_) => b'r',
+            Argument::Midi(_) => b'm',
+            Argument::Bool(true) => b'T',
+            Argument::Bool(false) => b'F',
+            Argument::Nil => b'N',
+            Argument::Infinitum => b'I',
+            Argument::Array(_) => b'[',
+        }
+    }
+}
+
+/// A borrowed OSC Command argument, referencing the buffer it was decoded from.
+#[derive(Debug, Clone)]
+pub enum ArgumentRef<'a> {
+    /// `s`: a string.
+    String(&'a str),
+    /// `i`: a 32-bit integer.
+    Integer(i32),
+    /// `f`: a 32-bit float.
+    Float(f32),
+    /// `b`: a blob of binary data.
+    Binary(&'a [u8]),
+    /// `h`: a 64-bit integer.
+    Long(i64),
+    /// `d`: a 64-bit float.
+    Double(f64),
+    /// `t`: a time tag.
+    Timetag(Timetag),
+    /// `S`: a symbol.
+    Symbol(&'a str),
+    /// `c`: a character.
+    Char(char),
+    /// `r`: a 32-bit RGBA color.
+    Color(Rgba),
+    /// `m`: a 4-byte MIDI message.
+    Midi(Midi),
+    /// `T` or `F`: a boolean.
+    Bool(bool),
+    /// `N`: nil.
+    Nil,
+    /// `I`: infinitum.
+    Infinitum,
+    /// `[` ... `]`: an array of arguments.
+    Array(ArrayRef<'a>),
+}
+
+impl<'a> ArgumentRef<'a> {
+
+    /// Decodes an argument of the provided type from a buffer, returning the argument, and the
+    /// remaining buffer after any padding.
+    ///
+    /// Array elements take their type tags from `tags`, which must be positioned just after
+    /// `type_tag`. Arrays nested more than [`MAX_ARRAY_DEPTH`](constant.MAX_ARRAY_DEPTH.html)
+    /// deep fail with `DecodeError::TooDeep`.
+    pub fn decode(type_tag: char,
+                  tags: &mut str::Chars<'a>,
+                  buf: &'a [u8],
+                  strict: bool) -> Result<(ArgumentRef<'a>, &'a [u8]), DecodeError> {
+        ArgumentRef::decode_nested(type_tag, tags, buf, strict, 1)
+    }
+
+    /// Decodes an argument that, if it is an array, is nested `depth` deep.
+    fn decode_nested(type_tag: char,
+                     tags: &mut str::Chars<'a>,
+                     buf: &'a [u8],
+                     strict: bool,
+                     depth: usize) -> Result<(ArgumentRef<'a>, &'a [u8]), DecodeError> {
+        match type_tag {
+            's' | 'S' => {
+                let (s, b) = decode_string(buf, strict)?;
+                if type_tag == 's' {
+                    Ok((ArgumentRef::String(s), b))
+                } else {
+                    Ok((ArgumentRef::Symbol(s), b))
+                }
+            },
+            'i' => {
+                let (i, b) = split(buf, 4)?;
+                Ok((ArgumentRef::Integer(BigEndian::read_i32(i)), b))
+            },
+            'f' => {
+                let (f, b) = split(buf, 4)?;
+                Ok((ArgumentRef::Float(BigEndian::read_f32(f)), b))
+            },
+            'b' => {
+                let (len, b) = split(buf, 4)?;
+                let len = BigEndian::read_u32(len);
+                if len as usize > b.len() {
+                    return Err(DecodeError::LengthOverflow { offset: 0, len });
+                }
+                let blob = &b[..len as usize];
+                let b = unpad(b, len as usize, strict).map_err(|e| e.offset_by(4))?;
+                Ok((ArgumentRef::Binary(blob), b))
+            },
+            'h' => {
+                let (h, b) = split(buf, 8)?;
+                Ok((ArgumentRef::Long(BigEndian::read_i64(h)), b))
+            },
+            'd' => {
+                let (d, b) = split(buf, 8)?;
+                Ok((ArgumentRef::Double(BigEndian::read_f64(d)), b))
+            },
+            't' => {
+                let (t, b) = split(buf, 8)?;
+                Ok((ArgumentRef::Timetag(Timetag::from_bits(BigEndian::read_u64(t))), b))
+            },
+            'c' => {
+                let (c, b) = split(buf, 4)?;
+                let c = char::from_u32(BigEndian::read_u32(c))
+                    .ok_or(DecodeError::InvalidChar { offset: 0 })?;
+                Ok((ArgumentRef::Char(c), b))
+            },
+            'r' => {
+                let (c, b) = split(buf, 4)?;
+                let c = Rgba { red: c[0], green: c[1], blue: c[2], alpha: c[3] };
+                Ok((ArgumentRef::Color(c), b))
+            },
+            'm' => {
+                let (m, b) = split(buf, 4)?;
+                let m = Midi { port: m[0], status: m[1], data1: m[2], data2: m[3] };
+                Ok((ArgumentRef::Midi(m), b))
+            },
+            'T' => Ok((ArgumentRef::Bool(true), buf)),
+            'F' => Ok((ArgumentRef::Bool(false), buf)),
+            'N' => Ok((ArgumentRef::Nil, buf)),
+            'I' => Ok((ArgumentRef::Infinitum, buf)),
+            '[' if depth > MAX_ARRAY_DEPTH => Err(DecodeError::TooDeep { offset: 0 }),
+            '[' => {
+                let offset = |b: &[u8]| buf.len() - b.len();
+                let array_tags = tags.as_str();
+                let mut b = buf;
+                loop {
+                    match tags.next() {
+                        Some(']') => {
+                            // Everything up to, but not including, the closing ']'.
+                            let tags_len = array_tags.len() - tags.as_str().len() - 1;
+                            let array = ArrayRef {
+                                tags: array_tags[..tags_len].chars(),
+                                buf: &buf[..offset(b)],
+                                strict,
+                                depth: depth + 1,
+                            };
+                            return Ok((ArgumentRef::Array(array), b));
+                        },
+                        Some(tag) => {
+                            // Decode the element to validate it and find where the next one
+                            // starts. The array itself decodes the elements again on demand.
+                            let (_, rest) = ArgumentRef::decode_nested(tag, tags, b, strict,
+                                                                       depth + 1)
+                                .map_err(|e| e.offset_by(offset(b)))?;
+                            b = rest;
+                        },
+                        None => return Err(DecodeError::UnbalancedArray { offset: 0 }),
+                    }
+                }
+            },
+            ']' => Err(DecodeError::UnbalancedArray { offset: 0 }),
+            _ => Err(DecodeError::UnknownTag { offset: 0, tag: type_tag }),
+        }
+    }
+
+    /// Copies the argument out of the buffer it references.
+    ///
+    /// Nested arrays are copied by recursion, which decoding has bounded to
+    /// [`MAX_ARRAY_DEPTH`](constant.MAX_ARRAY_DEPTH.html) levels.
+    #[cfg(feature = "alloc")]
+    pub fn to_owned(&self) -> Argument {
+        match *self {
+            ArgumentRef::String(s) => Argument::String(s.to_string()),
+            ArgumentRef::Integer(i) => Argument::Integer(i),
+            ArgumentRef::Float(f) => Argument::Float(f),
+            ArgumentRef::Binary(b) => Argument::Binary(b.to_vec()),
+            ArgumentRef::Long(h) => Argument::Long(h),
+            ArgumentRef::Double(d) => Argument::Double(d),
+            ArgumentRef::Timetag(t) => Argument::Timetag(t),
+            ArgumentRef::Symbol(s) => Argument::Symbol(s.to_string()),
+            ArgumentRef::Char(c) => Argument::Char(c),
+            ArgumentRef::Color(c) => Argument::Color(c),
+            ArgumentRef::Midi(m) => Argument::Midi(m),
+            ArgumentRef::Bool(b) => Argument::Bool(b),
+            ArgumentRef::Nil => Argument::Nil,
+            ArgumentRef::Infinitum => Argument::Infinitum,
+            ArgumentRef::Array(ref elements) => {
+                Argument::Array(elements.clone().map(|e| e.to_owned()).collect())
+            },
+        }
+    }
+}
+
+/// An iterator over the elements of a borrowed array argument.
+///
+/// The elements were validated when the array was decoded, and are decoded again as the
+/// iterator is advanced.
+#[derive(Debug, Clone)]
+pub struct ArrayRef<'a> {
+    tags: str::Chars<'a>,
+    buf: &'a [u8],
+    strict: bool,
+    /// How deep an array among the elements is nested.
+    depth: usize,
+}
+
+impl<'a> Iterator for ArrayRef<'a> {
+    type Item = ArgumentRef<'a>;
+
+    fn next(&mut self) -> Option<ArgumentRef<'a>> {
+        let type_tag = self.tags.next()?;
+        let (element, buf) = ArgumentRef::decode_nested(type_tag,
+                                                        &mut self.tags,
+                                                        self.buf,
+                                                        self.strict,
+                                                        self.depth)
+            .expect("array elements are validated when the array is decoded");
+        self.buf = buf;
+        Some(element)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Decodes a single argument with no array tags after it.
+    fn decode(tag: char, buf: &[u8]) -> Result<ArgumentRef<'_>, DecodeError> {
+        ArgumentRef::decode(tag, &mut "".chars(), buf, false).map(|(argument, _)| argument)
+    }
+
+    #[test]
+    fn truncated() {
+        for &(tag, len) in &[('i', 4), ('f', 4), ('c', 4), ('r', 4), ('m', 4), ('b', 4),
+                             ('h', 8), ('d', 8), ('t', 8)] {
+            assert_eq!(decode(tag, b"\0\0\0").unwrap_err(),
+                       DecodeError::Truncated { offset: 0, len });
+        }
+    }
+
+    #[test]
+    fn unterminated() {
+        assert_eq!(decode('s', b"abcd").unwrap_err(), DecodeError::Unterminated { offset: 0 });
+        assert_eq!(decode('S', b"").unwrap_err(), DecodeError::Unterminated { offset: 0 });
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        assert_eq!(decode('s', b"\xc3\x28\0\0").unwrap_err(),
+                   DecodeError::InvalidUtf8 { offset: 0 });
+    }
+
+    #[test]
+    fn unknown_tag() {
+        assert_eq!(decode('x', b"\0\0\0\x01").unwrap_err(),
+                   DecodeError::UnknownTag { offset: 0, tag: 'x' });
+    }
+
+    #[test]
+    fn length_overflow() {
+        assert_eq!(decode('b', b"\0\0\0\x05abcd").unwrap_err(),
+                   DecodeError::LengthOverflow { offset: 0, len: 5 });
+        assert_eq!(decode('b', b"\xff\xff\xff\xff").unwrap_err(),
+                   DecodeError::LengthOverflow { offset: 0, len: u32::MAX });
+    }
+
+    #[test]
+    fn invalid_char() {
+        assert_eq!(decode('c', b"\0\0\xd8\0").unwrap_err(),
+                   DecodeError::InvalidChar { offset: 0 });
+    }
+
+    #[test]
+    fn unbalanced_array() {
+        let mut tags = "i".chars();
+        let error = ArgumentRef::decode('[', &mut tags, b"\0\0\0\x01", false).unwrap_err();
+        assert_eq!(error, DecodeError::UnbalancedArray { offset: 0 });
+        assert_eq!(decode(']', b"").unwrap_err(), DecodeError::UnbalancedArray { offset: 0 });
+    }
+
+    #[test]
+    fn errors_in_array_elements_are_offset_from_the_array() {
+        let mut tags = "ix]".chars();
+        let error = ArgumentRef::decode('[', &mut tags, b"\0\0\0\x01\0\0\0\x02", false)
+            .unwrap_err();
+        assert_eq!(error, DecodeError::UnknownTag { offset: 4, tag: 'x' });
+
+        let mut tags = "i[s]]".chars();
+        let error = ArgumentRef::decode('[', &mut tags, b"\0\0\0\x01abcd", false).unwrap_err();
+        assert_eq!(error, DecodeError::Unterminated { offset: 4 });
+    }
+
+    /// Returns the type tags of `depth` arrays, each holding the next and the innermost
+    /// holding an integer.
+    fn nested_tags(depth: usize, buf: &mut [u8]) -> &str {
+        for (i, tag) in buf.iter_mut().enumerate() {
+            *tag = if i < depth { b'[' } else if i == depth { b'i' } else { b']' };
+        }
+        str::from_utf8(&buf[..2 * depth + 1]).unwrap()
+    }
+
+    #[test]
+    fn decodes_arrays_nested_to_the_maximum_depth() {
+        let mut tags = [0; 2 * MAX_ARRAY_DEPTH + 1];
+        let mut tags = nested_tags(MAX_ARRAY_DEPTH, &mut tags).chars();
+        let tag = tags.next().unwrap();
+        let (mut argument, rest) = ArgumentRef::decode(tag, &mut tags, b"\0\0\0\x07", false)
+            .unwrap();
+        assert!(rest.is_empty());
+        for _ in 0..MAX_ARRAY_DEPTH {
+            argument = match argument {
+                ArgumentRef::Array(mut elements) => elements.next().unwrap(),
+                _ => panic!("expected an array"),
+            };
+        }
+        assert!(matches!(argument, ArgumentRef::Integer(7)));
+    }
+
+    #[test]
+    fn rejects_arrays_nested_too_deep() {
+        let mut tags = [0; 2 * MAX_ARRAY_DEPTH + 3];
+        let mut tags = nested_tags(MAX_ARRAY_DEPTH + 1, &mut tags).chars();
+        let tag = tags.next().unwrap();
+        let error = ArgumentRef::decode(tag, &mut tags, b"\0\0\0\x07", false).unwrap_err();
+        assert_eq!(error, DecodeError::TooDeep { offset: 0 });
+
+        // Thousands of unclosed arrays, which would exhaust the stack without a limit.
+        let tags = [b'['; 5000];
+        let mut tags = str::from_utf8(&tags).unwrap().chars();
+        let tag = tags.next().unwrap();
+        let error = ArgumentRef::decode(tag, &mut tags, b"", false).unwrap_err();
+        assert_eq!(error, DecodeError::TooDeep { offset: 0 });
+    }
+
+    #[cfg(feature = "alloc")]
+    fn nested_argument(depth: usize) -> Argument {
+        (0..depth).fold(Argument::Integer(7), |argument, _| Argument::Array(Vec::from([argument])))
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn encodes_arrays_nested_to_the_maximum_depth() {
+        let argument = nested_argument(MAX_ARRAY_DEPTH);
+        let mut tags = Vec::new();
+        argument.encode_tags(&mut tags);
+        assert_eq!(tags.len(), argument.tags_len());
+        let mut buf = Vec::new();
+        argument.encode(&mut buf);
+        assert_eq!(buf, [0, 0, 0, 7]);
+
+        let mut tags = str::from_utf8(&tags).unwrap().chars();
+        let tag = tags.next().unwrap();
+        assert_eq!(Argument::decode(tag, &mut tags, &buf, false).unwrap().0, argument);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn encodes_arrays_nested_too_deep_to_decode() {
+        let depth = 1000;
+        let argument = nested_argument(depth);
+        assert_eq!(argument.tags_len(), 2 * depth + 1);
+        assert_eq!(argument.encoded_len(), 4);
+
+        let mut tags = Vec::new();
+        argument.encode_tags(&mut tags);
+        assert_eq!(tags.len(), 2 * depth + 1);
+        assert!(tags[..depth].iter().all(|&tag| tag == b'['));
+        assert_eq!(tags[depth], b'i');
+        assert!(tags[depth + 1..].iter().all(|&tag| tag == b']'));
+        let mut buf = Vec::new();
+        argument.encode(&mut buf);
+        assert_eq!(buf, [0, 0, 0, 7]);
+
+        let mut tags = str::from_utf8(&tags).unwrap().chars();
+        let tag = tags.next().unwrap();
+        let error = Argument::decode(tag, &mut tags, &buf, false).unwrap_err();
+        assert_eq!(error, DecodeError::TooDeep { offset: 0 });
+    }
+}
@@ -0,0 +1,42 @@
+//! A stand-in for an XR12 mixer, for running clients without the hardware.
+//!
+//! Listens on `127.0.0.1:10024`, or on the address given with `--bind`. Pass `--name` to change
+//! the name it reports in reply to `/info` and `/xinfo`.
+
+extern crate april_2018_challenge;
+
+use std::env;
+use std::process;
+
+use april_2018_challenge::Emulator;
+
+fn main() {
+    let mut bind = String::from("127.0.0.1:10024");
+    let mut name = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = match arg.as_str() {
+            "--bind" | "--name" => args.next(),
+            _ => {
+                eprintln!("error: unexpected argument {:?}", arg);
+                process::exit(2);
+            },
+        };
+        let value = value.unwrap_or_else(|| {
+            eprintln!("error: {} requires a value", arg);
+            process::exit(2);
+        });
+        match arg.as_str() {
+            "--bind" => bind = value,
+            _ => name = Some(value),
+        }
+    }
+
+    let mut emulator = Emulator::bind(bind.as_str()).expect("failed to bind UDP socket");
+    if let Some(name) = name {
+        emulator.set_name(name);
+    }
+    println!("emulating a mixer on {}", emulator.local_addr().expect("failed to get address"));
+    emulator.run().expect("failed to answer commands");
+}
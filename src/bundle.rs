@@ -0,0 +1,291 @@
+//! OSC bundles and packets.
+
+#[cfg(feature = "std")]
+use std::io;
+
+use alloc::vec::Vec;
+use byteorder::{BigEndian, ByteOrder};
+
+use {decode_string, split, MAX_BUNDLE_DEPTH};
+use command::Command;
+use encode::{self, put_string, Output};
+#[cfg(feature = "std")]
+use encode::WriteOutput;
+use error::{DecodeError, EncodeError};
+use timetag::Timetag;
+
+/// An OSC bundle: a time tag and a list of messages or nested bundles to be applied atomically.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bundle {
+    /// The time at which the bundle's contents should be applied.
+    pub timetag: Timetag,
+    /// The commands and nested bundles in the bundle.
+    pub elements: Vec<Packet>,
+}
+
+impl Bundle {
+
+    /// Encodes the bundle to a buffer.
+    pub fn encode(&self, buf: &mut Vec<u8>) {
+        encode::to_vec(buf, |out| self.put(out))
+    }
+
+    /// Encodes the bundle into a fixed-size buffer, returning the number of bytes written.
+    pub fn encode_to_slice(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        encode::to_slice(self.encoded_len(), buf, |out| self.put(out))
+    }
+
+    /// Encodes the bundle to a writer.
+    #[cfg(feature = "std")]
+    pub fn encode_to_writer<W: io::Write + ?Sized>(&self, w: &mut W) -> io::Result<()> {
+        self.put(&mut WriteOutput(w))
+    }
+
+    /// Returns the number of bytes the encoded bundle takes up.
+    pub fn encoded_len(&self) -> usize {
+        // "#bundle\0", the time tag, then each element prefixed by its size.
+        16 + self.elements.iter().map(|e| 4 + e.encoded_len()).sum::<usize>()
+    }
+
+    pub(crate) fn put<O: Output>(&self, out: &mut O) -> Result<(), O::Error> {
+        let mut b = [0; 8];
+        put_string("#bundle", out)?;
+        BigEndian::write_u64(&mut b, self.timetag.to_bits());
+        out.put(&b)?;
+
+        for element in &self.elements {
+            BigEndian::write_u32(&mut b, element.encoded_len() as u32);
+            out.put(&b[..4])?;
+            element.put(out)?;
+        }
+        Ok(())
+    }
+
+    /// Decodes a Bundle from a buffer.
+    pub fn decode(packet: &[u8]) -> Result<Bundle, DecodeError> {
+        Bundle::decode_with(packet, false)
+    }
+
+    /// Decodes a Bundle from a buffer, rejecting padding that contains non-zero bytes.
+    pub fn decode_strict(packet: &[u8]) -> Result<Bundle, DecodeError> {
+        Bundle::decode_with(packet, true)
+    }
+
+    pub(crate) fn decode_with(packet: &[u8], strict: bool) -> Result<Bundle, DecodeError> {
+        Bundle::decode_nested(packet, strict, 1)
+    }
+
+    /// Decodes a bundle nested `depth` deep, counting the outermost bundle as 1.
+    fn decode_nested(packet: &[u8], strict: bool, depth: usize) -> Result<Bundle, DecodeError> {
+        let offset = |buf: &[u8]| packet.len() - buf.len();
+        if depth > MAX_BUNDLE_DEPTH {
+            return Err(DecodeError::TooDeep { offset: 0 });
+        }
+
+        let (tag, buf) = decode_string(packet, strict)?;
+        if tag != "#bundle" {
+            return Err(DecodeError::NotBundle { offset: 0 });
+        }
+
+        let (timetag, mut buf) = split(buf, 8).map_err(|e| e.offset_by(offset(buf)))?;
+        let timetag = Timetag::from_bits(BigEndian::read_u64(timetag));
+
+        let mut elements = Vec::new();
+        while !buf.is_empty() {
+            let size_offset = offset(buf);
+            let (size, b) = split(buf, 4).map_err(|e| e.offset_by(size_offset))?;
+            let size = BigEndian::read_u32(size);
+            if size as usize > b.len() {
+                return Err(DecodeError::LengthOverflow { offset: size_offset, len: size });
+            }
+            if size % 4 != 0 {
+                return Err(DecodeError::BadPadding { offset: size_offset });
+            }
+            let (element, b) = b.split_at(size as usize);
+            let element = Packet::decode_nested(element, strict, depth + 1)
+                .map_err(|e| e.offset_by(size_offset + 4))?;
+            elements.push(element);
+            buf = b;
+        }
+
+        Ok(Bundle {
+            timetag,
+            elements,
+        })
+    }
+}
+
+/// An OSC packet: either a single command or a bundle.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Packet {
+    /// A single command.
+    Command(Command),
+    /// A bundle of commands and nested bundles.
+    Bundle(Bundle),
+}
+
+impl Packet {
+
+    /// Encodes the packet to a buffer.
+    pub fn encode(&self, buf: &mut Vec<u8>) {
+        encode::to_vec(buf, |out| self.put(out))
+    }
+
+    /// Encodes the packet into a fixed-size buffer, returning the number of bytes written.
+    pub fn encode_to_slice(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        encode::to_slice(self.encoded_len(), buf, |out| self.put(out))
+    }
+
+    /// Encodes the packet to a writer.
+    #[cfg(feature = "std")]
+    pub fn encode_to_writer<W: io::Write + ?Sized>(&self, w: &mut W) -> io::Result<()> {
+        self.put(&mut WriteOutput(w))
+    }
+
+    /// Returns the number of bytes the encoded packet takes up.
+    pub fn encoded_len(&self) -> usize {
+        match self {
+            Packet::Command(c) => c.encoded_len(),
+            Packet::Bundle(b) => b.encoded_len(),
+        }
+    }
+
+    pub(crate) fn put<O: Output>(&self, out: &mut O) -> Result<(), O::Error> {
+        match self {
+            Packet::Command(c) => c.put(out),
+            Packet::Bundle(b) => b.put(out),
+        }
+    }
+
+    /// Decodes a Packet from a buffer.
+    pub fn decode(buf: &[u8]) -> Result<Packet, DecodeError> {
+        Packet::decode_with(buf, false)
+    }
+
+    /// Decodes a Packet from a buffer, rejecting padding that contains non-zero bytes.
+    pub fn decode_strict(buf: &[u8]) -> Result<Packet, DecodeError> {
+        Packet::decode_with(buf, true)
+    }
+
+    pub(crate) fn decode_with(buf: &[u8], strict: bool) -> Result<Packet, DecodeError> {
+        Packet::decode_nested(buf, strict, 1)
+    }
+
+    /// Returns an iterator over the commands in the packet, including those in nested bundles,
+    /// in the order they appear.
+    ///
+    /// ```
+    /// use april_2018_challenge::{Bundle, Command, Packet, Timetag};
+    ///
+    /// let inner = Bundle { timetag: Timetag::IMMEDIATELY, elements: vec![
+    ///     Command::new("/b").into(),
+    /// ] };
+    /// let packet = Packet::Bundle(Bundle { timetag: Timetag::IMMEDIATELY, elements: vec![
+    ///     Command::new("/a").into(),
+    ///     inner.into(),
+    ///     Command::new("/c").into(),
+    /// ] });
+    ///
+    /// let addresses: Vec<&str> = packet.commands()
+    ///     .map(|command| command.address_pattern.as_str())
+    ///     .collect();
+    /// assert_eq!(addresses, ["/a", "/b", "/c"]);
+    /// ```
+    pub fn commands(&self) -> Commands<'_> {
+        Commands { pending: Vec::from([self]) }
+    }
+
+    /// Decodes a packet that, if it is a bundle, is nested `depth` deep.
+    fn decode_nested(buf: &[u8], strict: bool, depth: usize) -> Result<Packet, DecodeError> {
+        if buf.starts_with(b"#bundle\0") {
+            Bundle::decode_nested(buf, strict, depth).map(Packet::Bundle)
+        } else {
+            Command::decode_with(buf, strict).map(Packet::Command)
+        }
+    }
+}
+
+/// An iterator over the commands in a `Packet`, returned by
+/// [`Packet::commands`](enum.Packet.html#method.commands).
+#[derive(Debug, Clone)]
+pub struct Commands<'a> {
+    /// The packets still to visit, last first, so nesting does not grow the stack.
+    pending: Vec<&'a Packet>,
+}
+
+impl<'a> Iterator for Commands<'a> {
+    type Item = &'a Command;
+
+    fn next(&mut self) -> Option<&'a Command> {
+        while let Some(packet) = self.pending.pop() {
+            match packet {
+                Packet::Command(command) => return Some(command),
+                Packet::Bundle(bundle) => self.pending.extend(bundle.elements.iter().rev()),
+            }
+        }
+        None
+    }
+}
+
+impl From<Command> for Packet {
+    fn from(command: Command) -> Packet {
+        Packet::Command(command)
+    }
+}
+
+impl From<Bundle> for Packet {
+    fn from(bundle: Bundle) -> Packet {
+        Packet::Bundle(bundle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use dump::hex_dump;
+
+    /// Returns a command wrapped in `depth` bundles, each holding the next.
+    fn nested(depth: usize) -> Vec<u8> {
+        let mut packet = Vec::new();
+        Command::new("/a").arg(1).encode(&mut packet);
+        for _ in 0..depth {
+            let mut bundle = Vec::new();
+            put_string("#bundle", &mut bundle).unwrap();
+            bundle.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+            bundle.extend_from_slice(&(packet.len() as u32).to_be_bytes());
+            bundle.extend_from_slice(&packet);
+            packet = bundle;
+        }
+        packet
+    }
+
+    #[test]
+    fn decodes_bundles_nested_to_the_maximum_depth() {
+        let mut packet = Packet::decode(&nested(MAX_BUNDLE_DEPTH)).unwrap();
+        for _ in 0..MAX_BUNDLE_DEPTH {
+            packet = match packet {
+                Packet::Bundle(mut bundle) => bundle.elements.remove(0),
+                Packet::Command(_) => panic!("expected a bundle"),
+            };
+        }
+        assert_eq!(packet, Packet::Command(Command::new("/a").arg(1)));
+    }
+
+    #[test]
+    fn rejects_bundles_nested_too_deep() {
+        // Each bundle adds 20 bytes before the one nested in it.
+        let error = DecodeError::TooDeep { offset: 20 * MAX_BUNDLE_DEPTH };
+        assert_eq!(Packet::decode(&nested(MAX_BUNDLE_DEPTH + 1)), Err(error.clone()));
+        assert_eq!(Packet::decode(&nested(3200)), Err(error.clone()));
+        assert_eq!(Bundle::decode_strict(&nested(3200)), Err(error));
+    }
+
+    #[test]
+    fn dumps_bundles_nested_too_deep() {
+        let dump = hex_dump(&nested(3200)).to_string();
+        assert_eq!(dump.lines().filter(|line| line.contains("#bundle")).count(),
+                   MAX_BUNDLE_DEPTH);
+        assert!(dump.contains("error: bundle or array at offset 640 is nested too deep"));
+    }
+}
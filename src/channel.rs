@@ -0,0 +1,409 @@
+//! Reading and changing the settings of an input channel.
+
+use std::io;
+
+use alloc::string::String;
+
+use address::{Address, Parameter, Strip};
+use argument::Argument;
+use client::MixerClient;
+use eq::Equalizer;
+use fader::{db_to_fader, fader_to_db};
+use setting::{bool_argument, clamp_unit, from_log_scale, get, get_bool, get_float, to_log_scale,
+              unexpected};
+
+/// The lowest head amp gain, in dB.
+pub const MIN_GAIN_DB: f32 = -12.0;
+/// The highest head amp gain, in dB.
+pub const MAX_GAIN_DB: f32 = 60.0;
+/// The lowest low cut frequency, in Hz.
+pub const MIN_LOW_CUT_HZ: f32 = 20.0;
+/// The highest low cut frequency, in Hz.
+pub const MAX_LOW_CUT_HZ: f32 = 400.0;
+
+/// The color of a strip's scribble strip. The inverted colors show dark text on a lit
+/// background.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StripColor {
+    Off,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    OffInverted,
+    RedInverted,
+    GreenInverted,
+    YellowInverted,
+    BlueInverted,
+    MagentaInverted,
+    CyanInverted,
+    WhiteInverted,
+}
+
+/// Every color, in the order of the numbers the mixer uses for them.
+const COLORS: [StripColor; 16] = [
+    StripColor::Off,
+    StripColor::Red,
+    StripColor::Green,
+    StripColor::Yellow,
+    StripColor::Blue,
+    StripColor::Magenta,
+    StripColor::Cyan,
+    StripColor::White,
+    StripColor::OffInverted,
+    StripColor::RedInverted,
+    StripColor::GreenInverted,
+    StripColor::YellowInverted,
+    StripColor::BlueInverted,
+    StripColor::MagentaInverted,
+    StripColor::CyanInverted,
+    StripColor::WhiteInverted,
+];
+
+impl StripColor {
+
+    /// Returns the color with the number the mixer uses for it, from 0 to 15.
+    pub fn from_index(index: i32) -> Option<StripColor> {
+        if index < 0 {
+            return None;
+        }
+        COLORS.get(index as usize).cloned()
+    }
+
+    /// Returns the number the mixer uses for the color, from 0 to 15.
+    pub fn index(self) -> i32 {
+        self as i32
+    }
+}
+
+/// The settings of one input channel, read and changed through a
+/// [`MixerClient`](struct.MixerClient.html). Created by
+/// [`MixerClient::channel`](struct.MixerClient.html#method.channel).
+///
+/// Each setting is read by querying the mixer and changed by sending it a command, with the
+/// values converted to and from the units the mixer shows, such as dB and Hz. Values outside a
+/// setting's range are clamped to it. Reading fails with `ErrorKind::InvalidData` if the reply
+/// does not hold the type of value expected.
+///
+/// The gain and phantom power are those of the head amp with the channel's number, which
+/// feeds the channel unless its input has been routed elsewhere.
+///
+/// ```
+/// use std::net::UdpSocket;
+/// use std::thread;
+///
+/// use april_2018_challenge::{Emulator, MixerClient, StripColor};
+///
+/// let mut emulator = Emulator::bind("127.0.0.1:0").unwrap();
+/// let addr = emulator.local_addr().unwrap();
+/// thread::spawn(move || emulator.run());
+///
+/// let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+/// socket.connect(addr).unwrap();
+/// let mut client = MixerClient::new(socket);
+/// let mut vocals = client.channel(3).unwrap();
+/// vocals.set_name("Vocals").unwrap();
+/// vocals.set_color(StripColor::Magenta).unwrap();
+/// vocals.set_pan(-50.0).unwrap();
+/// vocals.set_low_cut_frequency(100.0).unwrap();
+///
+/// assert_eq!(vocals.name().unwrap(), "Vocals");
+/// assert_eq!(vocals.color().unwrap(), StripColor::Magenta);
+/// assert_eq!(vocals.pan().unwrap(), -50.0);
+/// assert!((vocals.low_cut_frequency().unwrap() - 100.0).abs() < 0.01);
+/// ```
+#[derive(Debug)]
+pub struct ChannelStrip<'a> {
+    client: &'a mut MixerClient,
+    channel: u8,
+}
+
+impl<'a> ChannelStrip<'a> {
+
+    pub(crate) fn new(client: &'a mut MixerClient, channel: u8) -> io::Result<ChannelStrip<'a>> {
+        Strip::Channel(channel).validate()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        Ok(ChannelStrip { client, channel })
+    }
+
+    /// Returns the channel's number, starting at 1.
+    pub fn channel(&self) -> u8 {
+        self.channel
+    }
+
+    /// Returns the address of one of the channel's parameters.
+    pub fn address(&self, parameter: Parameter) -> Address {
+        // Every parameter exists on a channel whose number has been validated.
+        Address::new(Strip::Channel(self.channel), parameter).expect("invalid channel parameter")
+    }
+
+    /// Returns the channel's parametric EQ.
+    pub fn eq(&mut self) -> Equalizer<'_> {
+        // Every channel has an EQ.
+        Equalizer::new(self.client, Strip::Channel(self.channel)).expect("channel without an EQ")
+    }
+
+    /// Returns the level of the fader, in dB.
+    pub fn fader_db(&mut self) -> io::Result<f32> {
+        self.get_float(Parameter::Fader).map(fader_to_db)
+    }
+
+    /// Moves the fader to the position nearest to `db`.
+    pub fn set_fader_db(&mut self, db: f32) -> io::Result<()> {
+        self.set(Parameter::Fader, db_to_fader(db).into())
+    }
+
+    /// Returns whether the channel is muted.
+    pub fn mute(&mut self) -> io::Result<bool> {
+        self.get_bool(Parameter::On).map(|on| !on)
+    }
+
+    /// Mutes or unmutes the channel.
+    pub fn set_mute(&mut self, muted: bool) -> io::Result<()> {
+        self.set_bool(Parameter::On, !muted)
+    }
+
+    /// Returns the pan, from -100 (left) to 100 (right).
+    pub fn pan(&mut self) -> io::Result<f32> {
+        self.get_float(Parameter::Pan).map(|value| value * 200.0 - 100.0)
+    }
+
+    /// Sets the pan, from -100 (left) to 100 (right).
+    pub fn set_pan(&mut self, pan: f32) -> io::Result<()> {
+        self.set(Parameter::Pan, clamp_unit((pan + 100.0) / 200.0).into())
+    }
+
+    /// Returns the gain of the head amp, from -12 dB to +60 dB.
+    pub fn gain(&mut self) -> io::Result<f32> {
+        let range = MAX_GAIN_DB - MIN_GAIN_DB;
+        self.get_float(Parameter::Gain).map(|value| MIN_GAIN_DB + value * range)
+    }
+
+    /// Sets the gain of the head amp, from -12 dB to +60 dB.
+    pub fn set_gain(&mut self, db: f32) -> io::Result<()> {
+        let range = MAX_GAIN_DB - MIN_GAIN_DB;
+        self.set(Parameter::Gain, clamp_unit((db - MIN_GAIN_DB) / range).into())
+    }
+
+    /// Returns whether the head amp supplies phantom power.
+    pub fn phantom(&mut self) -> io::Result<bool> {
+        self.get_bool(Parameter::Phantom)
+    }
+
+    /// Turns the head amp's phantom power on or off.
+    pub fn set_phantom(&mut self, on: bool) -> io::Result<()> {
+        self.set_bool(Parameter::Phantom, on)
+    }
+
+    /// Returns the channel's name.
+    pub fn name(&mut self) -> io::Result<String> {
+        match self.get(Parameter::Name)? {
+            Argument::String(name) => Ok(name),
+            argument => Err(unexpected(self.address(Parameter::Name), &argument)),
+        }
+    }
+
+    /// Sets the channel's name.
+    pub fn set_name(&mut self, name: &str) -> io::Result<()> {
+        self.set(Parameter::Name, name.into())
+    }
+
+    /// Returns the color of the channel's scribble strip.
+    pub fn color(&mut self) -> io::Result<StripColor> {
+        let address = self.address(Parameter::Color);
+        match self.get(Parameter::Color)? {
+            Argument::Integer(index) => {
+                StripColor::from_index(index)
+                    .ok_or_else(|| unexpected(address, &Argument::Integer(index)))
+            },
+            argument => Err(unexpected(address, &argument)),
+        }
+    }
+
+    /// Sets the color of the channel's scribble strip.
+    pub fn set_color(&mut self, color: StripColor) -> io::Result<()> {
+        self.set(Parameter::Color, color.index().into())
+    }
+
+    /// Returns whether the low cut filter is on.
+    pub fn low_cut(&mut self) -> io::Result<bool> {
+        self.get_bool(Parameter::LowCut)
+    }
+
+    /// Turns the low cut filter on or off.
+    pub fn set_low_cut(&mut self, on: bool) -> io::Result<()> {
+        self.set_bool(Parameter::LowCut, on)
+    }
+
+    /// Returns the frequency of the low cut filter, from 20 Hz to 400 Hz.
+    pub fn low_cut_frequency(&mut self) -> io::Result<f32> {
+        self.get_float(Parameter::LowCutFrequency)
+            .map(|value| from_log_scale(value, MIN_LOW_CUT_HZ, MAX_LOW_CUT_HZ))
+    }
+
+    /// Sets the frequency of the low cut filter, from 20 Hz to 400 Hz.
+    pub fn set_low_cut_frequency(&mut self, hz: f32) -> io::Result<()> {
+        let value = to_log_scale(hz, MIN_LOW_CUT_HZ, MAX_LOW_CUT_HZ);
+        self.set(Parameter::LowCutFrequency, value.into())
+    }
+
+    /// Returns whether the channel's polarity is inverted.
+    pub fn invert(&mut self) -> io::Result<bool> {
+        self.get_bool(Parameter::Invert)
+    }
+
+    /// Inverts the channel's polarity, or puts it back.
+    pub fn set_invert(&mut self, inverted: bool) -> io::Result<()> {
+        self.set_bool(Parameter::Invert, inverted)
+    }
+
+    fn get(&mut self, parameter: Parameter) -> io::Result<Argument> {
+        let address = self.address(parameter);
+        get(self.client, address)
+    }
+
+    fn get_float(&mut self, parameter: Parameter) -> io::Result<f32> {
+        let address = self.address(parameter);
+        get_float(self.client, address)
+    }
+
+    fn get_bool(&mut self, parameter: Parameter) -> io::Result<bool> {
+        let address = self.address(parameter);
+        get_bool(self.client, address)
+    }
+
+    fn set(&mut self, parameter: Parameter, value: Argument) -> io::Result<()> {
+        let command = self.address(parameter).set(value);
+        self.client.send(&command)
+    }
+
+    fn set_bool(&mut self, parameter: Parameter, on: bool) -> io::Result<()> {
+        self.set(parameter, bool_argument(on))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use emulator;
+    use fader::MAX_FADER_DB;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-3, "{} is not close to {}", actual, expected);
+    }
+
+    /// Returns the value the emulator holds for one of a channel's parameters.
+    fn raw(strip: &mut ChannelStrip, parameter: Parameter) -> Argument {
+        strip.get(parameter).unwrap()
+    }
+
+    fn raw_float(strip: &mut ChannelStrip, parameter: Parameter) -> f32 {
+        match raw(strip, parameter) {
+            Argument::Float(value) => value,
+            argument => panic!("expected a float, got {:?}", argument),
+        }
+    }
+
+    #[test]
+    fn scales_the_gain() {
+        let mut client = emulator::start();
+        let mut strip = client.channel(1).unwrap();
+        for &(db, value) in &[(-12.0, 0.0), (24.0, 0.5), (60.0, 1.0), (-20.0, 0.0), (70.0, 1.0)] {
+            strip.set_gain(db).unwrap();
+            assert_close(raw_float(&mut strip, Parameter::Gain), value);
+        }
+        strip.set(Parameter::Gain, 0.25.into()).unwrap();
+        assert_close(strip.gain().unwrap(), 6.0);
+    }
+
+    #[test]
+    fn scales_the_pan() {
+        let mut client = emulator::start();
+        let mut strip = client.channel(2).unwrap();
+        for &(pan, value) in &[(-100.0, 0.0), (0.0, 0.5), (100.0, 1.0), (-150.0, 0.0),
+                               (150.0, 1.0), (f32::NAN, 0.0)] {
+            strip.set_pan(pan).unwrap();
+            assert_close(raw_float(&mut strip, Parameter::Pan), value);
+        }
+        strip.set(Parameter::Pan, 0.75.into()).unwrap();
+        assert_close(strip.pan().unwrap(), 50.0);
+    }
+
+    #[test]
+    fn scales_the_low_cut_frequency() {
+        let mut client = emulator::start();
+        let mut strip = client.channel(3).unwrap();
+        for &(hz, value) in &[(20.0, 0.0), (400.0, 1.0), (10.0, 0.0), (0.0, 0.0), (1000.0, 1.0)] {
+            strip.set_low_cut_frequency(hz).unwrap();
+            assert_close(raw_float(&mut strip, Parameter::LowCutFrequency), value);
+        }
+        // Halfway along the log scale is the geometric mean of the ends.
+        strip.set(Parameter::LowCutFrequency, 0.5.into()).unwrap();
+        assert_close(strip.low_cut_frequency().unwrap(), (20.0f32 * 400.0).sqrt());
+        strip.set(Parameter::LowCutFrequency, 2.0.into()).unwrap();
+        assert_close(strip.low_cut_frequency().unwrap(), MAX_LOW_CUT_HZ);
+    }
+
+    #[test]
+    fn scales_the_fader() {
+        let mut client = emulator::start();
+        let mut strip = client.channel(4).unwrap();
+        for &(db, value) in &[(0.0, db_to_fader(0.0)), (10.0, 1.0), (20.0, 1.0),
+                              (f32::NEG_INFINITY, 0.0)] {
+            strip.set_fader_db(db).unwrap();
+            assert_eq!(raw_float(&mut strip, Parameter::Fader), value);
+        }
+        strip.set(Parameter::Fader, 1.0.into()).unwrap();
+        assert_eq!(strip.fader_db().unwrap(), MAX_FADER_DB);
+        strip.set(Parameter::Fader, 0.0.into()).unwrap();
+        assert_eq!(strip.fader_db().unwrap(), f32::NEG_INFINITY);
+    }
+
+    #[test]
+    fn maps_switches_to_integers() {
+        let mut client = emulator::start();
+        let mut strip = client.channel(5).unwrap();
+
+        // Muting turns the channel's `on` switch off.
+        strip.set_mute(true).unwrap();
+        assert_eq!(raw(&mut strip, Parameter::On), Argument::Integer(0));
+        assert!(strip.mute().unwrap());
+        strip.set_mute(false).unwrap();
+        assert_eq!(raw(&mut strip, Parameter::On), Argument::Integer(1));
+        assert!(!strip.mute().unwrap());
+
+        check_switch(&mut strip, Parameter::Phantom, ChannelStrip::phantom,
+                     ChannelStrip::set_phantom);
+        check_switch(&mut strip, Parameter::Invert, ChannelStrip::invert,
+                     ChannelStrip::set_invert);
+        check_switch(&mut strip, Parameter::LowCut, ChannelStrip::low_cut,
+                     ChannelStrip::set_low_cut);
+    }
+
+    /// Checks that a switch is set to 1 when on and 0 when off, and read back the same way.
+    fn check_switch<'a>(strip: &mut ChannelStrip<'a>,
+                        parameter: Parameter,
+                        get: fn(&mut ChannelStrip<'a>) -> io::Result<bool>,
+                        set: fn(&mut ChannelStrip<'a>, bool) -> io::Result<()>) {
+        for &on in &[true, false] {
+            set(strip, on).unwrap();
+            assert_eq!(raw(strip, parameter), Argument::Integer(on as i32));
+            assert_eq!(get(strip).unwrap(), on);
+        }
+    }
+
+    #[test]
+    fn rejects_unexpected_values() {
+        let mut client = emulator::start();
+        let mut strip = client.channel(6).unwrap();
+        strip.set(Parameter::Phantom, 2.into()).unwrap();
+        assert_eq!(strip.phantom().unwrap_err().kind(), io::ErrorKind::InvalidData);
+        strip.set(Parameter::Gain, "loud".into()).unwrap();
+        assert_eq!(strip.gain().unwrap_err().kind(), io::ErrorKind::InvalidData);
+        strip.set(Parameter::Color, 16.into()).unwrap();
+        assert_eq!(strip.color().unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
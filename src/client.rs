@@ -0,0 +1,469 @@
+//! Asking a mixer for values over UDP and matching up its replies.
+
+use std::collections::VecDeque;
+use std::io;
+use std::net::UdpSocket;
+use std::time::{Duration, Instant};
+
+use alloc::format;
+use alloc::vec::Vec;
+
+use address::{Address, Parameter, Strip};
+use argument::Argument;
+use bundle::Packet;
+use channel::ChannelStrip;
+use command::Command;
+use eq::Equalizer;
+use fader::{db_to_fader, fader_to_db};
+use subscription::{MeterBank, Meters, Remote};
+
+/// How long to wait for the first reply to a query before sending it again.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_millis(250);
+/// How many times a query is sent again before giving up.
+pub const DEFAULT_RETRIES: u32 = 3;
+/// The most unsolicited packets held at once. The oldest is dropped to make room for another.
+const MAX_UNSOLICITED: usize = 256;
+/// The largest packet that can be received.
+const MAX_PACKET_SIZE: usize = 65536;
+
+/// Sends commands to a mixer and waits for its replies.
+///
+/// UDP packets can be lost, so [`query`](#method.query) sends a request again if no reply
+/// arrives in time, waiting twice as long after each attempt. A reply is recognized by having
+/// the same address pattern as the request. Anything else received while waiting, such as
+/// meter data, is kept for [`recv`](#method.recv) instead.
+///
+/// ```
+/// use std::net::UdpSocket;
+/// use std::thread;
+///
+/// use april_2018_challenge::{Command, MixerClient};
+///
+/// // A stand-in for a mixer, which pushes a meter update before answering.
+/// let mixer = UdpSocket::bind("127.0.0.1:0").unwrap();
+/// let addr = mixer.local_addr().unwrap();
+/// thread::spawn(move || {
+///     let mut buf = [0; 512];
+///     let (_, from) = mixer.recv_from(&mut buf).unwrap();
+///     for reply in &[Command::new("/meters/1").arg(vec![0u8; 8]),
+///                    Command::new("/ch/01/mix/fader").arg(0.75)] {
+///         let mut buf = Vec::new();
+///         reply.encode(&mut buf);
+///         mixer.send_to(&buf, from).unwrap();
+///     }
+/// });
+///
+/// let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+/// socket.connect(addr).unwrap();
+/// let mut client = MixerClient::new(socket);
+/// let reply = client.query("/ch/01/mix/fader").unwrap();
+/// assert_eq!(reply, Command::new("/ch/01/mix/fader").arg(0.75));
+/// assert_eq!(Command::decode(client.last_packet()).unwrap(), reply);
+/// assert_eq!(client.unsolicited(), 1);
+/// ```
+#[derive(Debug)]
+pub struct MixerClient {
+    socket: UdpSocket,
+    timeout: Duration,
+    retries: u32,
+    /// Packets received that were not a reply to a query, oldest first, as they were received.
+    unsolicited: VecDeque<Vec<u8>>,
+    /// The packet being sent.
+    out: Vec<u8>,
+    /// The packet being received.
+    buf: Vec<u8>,
+    /// The packet last returned by `recv` or `request`, as it was received.
+    last: Vec<u8>,
+}
+
+impl MixerClient {
+
+    /// Wraps a UDP socket that is already connected to the mixer.
+    pub fn new(socket: UdpSocket) -> MixerClient {
+        let mut client = MixerClient {
+            socket,
+            timeout: DEFAULT_TIMEOUT,
+            retries: DEFAULT_RETRIES,
+            unsolicited: VecDeque::new(),
+            out: Vec::new(),
+            buf: Vec::new(),
+            last: Vec::new(),
+        };
+        client.buf.resize(MAX_PACKET_SIZE, 0);
+        client
+    }
+
+    /// Returns how long to wait for the first reply to a query, and for `recv`.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Sets how long to wait for the first reply to a query, and for `recv`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `timeout` is zero.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        assert!(timeout > Duration::from_secs(0), "timeout must not be zero");
+        self.timeout = timeout;
+    }
+
+    /// Returns how many times a query is sent again before giving up.
+    pub fn retries(&self) -> u32 {
+        self.retries
+    }
+
+    /// Sets how many times a query is sent again before giving up.
+    pub fn set_retries(&mut self, retries: u32) {
+        self.retries = retries;
+    }
+
+    /// Returns a reference to the underlying socket.
+    pub fn get_ref(&self) -> &UdpSocket {
+        &self.socket
+    }
+
+    /// Unwraps the underlying socket. Any unsolicited packets not yet returned are lost.
+    pub fn into_inner(self) -> UdpSocket {
+        self.socket
+    }
+
+    /// Sends a command without waiting for a reply.
+    pub fn send(&mut self, command: &Command) -> io::Result<()> {
+        self.out.clear();
+        command.encode(&mut self.out);
+        self.socket.send(&self.out).map(|_| ())
+    }
+
+    /// Asks the mixer for the value at `address`, and returns its reply.
+    pub fn query(&mut self, address: &str) -> io::Result<Command> {
+        self.request(&Command::new(address))
+    }
+
+    /// Sends a command and returns the mixer's reply, which is the first command received with
+    /// the same address pattern.
+    ///
+    /// The command is sent up to `retries + 1` times, waiting `timeout` for a reply to the
+    /// first and twice as long as the last time for each after that. Fails with
+    /// `ErrorKind::TimedOut` if no reply arrives. A reply to an earlier attempt that arrives
+    /// after a later one is kept as an unsolicited packet.
+    pub fn request(&mut self, command: &Command) -> io::Result<Command> {
+        let mut wait = self.timeout;
+        for _ in 0..=self.retries {
+            self.send(command)?;
+            let deadline = Instant::now() + wait;
+            while let Some((packet, len)) = self.recv_until(deadline)? {
+                match packet {
+                    Packet::Command(reply) if reply.address_pattern == command.address_pattern => {
+                        self.last.clear();
+                        self.last.extend_from_slice(&self.buf[..len]);
+                        return Ok(reply);
+                    },
+                    _ => {
+                        let packet = self.buf[..len].to_vec();
+                        self.push_unsolicited(packet);
+                    },
+                }
+            }
+            wait *= 2;
+        }
+        let message = format!("no reply to {} from the mixer", command.address_pattern);
+        Err(io::Error::new(io::ErrorKind::TimedOut, message))
+    }
+
+    /// Moves a strip's fader to the position nearest to `db`. See
+    /// [`db_to_fader`](fn.db_to_fader.html).
+    ///
+    /// Fails with `ErrorKind::InvalidInput` if the strip's number is out of range.
+    pub fn set_fader_db(&mut self, strip: Strip, db: f32) -> io::Result<()> {
+        let fader = fader_address(strip)?;
+        self.send(&fader.set(db_to_fader(db)))
+    }
+
+    /// Asks the mixer for the level of a strip's fader, in dB. See
+    /// [`fader_to_db`](fn.fader_to_db.html).
+    ///
+    /// Fails with `ErrorKind::InvalidInput` if the strip's number is out of range, and with
+    /// `ErrorKind::InvalidData` if the reply is not a single float.
+    pub fn fader_db(&mut self, strip: Strip) -> io::Result<f32> {
+        let fader = fader_address(strip)?;
+        let reply = self.request(&fader.get())?;
+        match reply.arguments[..] {
+            [Argument::Float(value)] => Ok(fader_to_db(value)),
+            _ => {
+                let message = format!("unexpected fader value: {}", reply);
+                Err(io::Error::new(io::ErrorKind::InvalidData, message))
+            },
+        }
+    }
+
+    /// Returns the settings of an input channel, numbered from 1.
+    ///
+    /// Fails with `ErrorKind::InvalidInput` if no X Air mixer has the channel.
+    pub fn channel(&mut self, channel: u8) -> io::Result<ChannelStrip<'_>> {
+        ChannelStrip::new(self, channel)
+    }
+
+    /// Returns the parametric EQ of a channel, bus or the main mix.
+    ///
+    /// Fails with `ErrorKind::InvalidInput` if no X Air mixer has the strip, or it has no EQ.
+    pub fn eq(&mut self, strip: Strip) -> io::Result<Equalizer<'_>> {
+        Equalizer::new(self, strip)
+    }
+
+    /// Returns the number of unsolicited packets waiting to be returned by `recv`.
+    pub fn unsolicited(&self) -> usize {
+        self.unsolicited.len()
+    }
+
+    /// Returns the oldest unsolicited packet, or if there are none, waits up to `timeout` for
+    /// the next packet from the mixer.
+    ///
+    /// Fails with `ErrorKind::TimedOut` if nothing arrives in time, and with
+    /// `ErrorKind::InvalidData` if a packet arrives that cannot be decoded.
+    pub fn recv(&mut self) -> io::Result<Packet> {
+        if let Some(packet) = self.unsolicited.pop_front() {
+            self.last = packet;
+        } else {
+            self.socket.set_read_timeout(Some(self.timeout))?;
+            let len = self.socket.recv(&mut self.buf).map_err(timed_out)?;
+            self.last.clear();
+            self.last.extend_from_slice(&self.buf[..len]);
+        }
+        Packet::decode(&self.last).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Returns the bytes of the packet last returned by `recv` or `request`, exactly as they
+    /// were received, or of the packet `recv` last failed to decode. Empty if nothing has been
+    /// received yet.
+    ///
+    /// The commands that [`Remote`](struct.Remote.html) and [`Meters`](struct.Meters.html)
+    /// return from the same bundle all came from that bundle's packet.
+    pub fn last_packet(&self) -> &[u8] {
+        &self.last
+    }
+
+    /// Subscribes to the changes others make to the mixer, by sending `/xremote` from a
+    /// background thread until the returned iterator is dropped.
+    pub fn remote(&mut self) -> io::Result<Remote<'_>> {
+        Remote::new(self)
+    }
+
+    /// Subscribes to one or more banks of meters, renewing the subscriptions from a background
+    /// thread until the returned iterator is dropped.
+    pub fn meters(&mut self, banks: &[MeterBank]) -> io::Result<Meters<'_>> {
+        Meters::new(self, banks)
+    }
+
+    /// Receives the next packet that can be decoded, and its length in `buf`, or `None` if
+    /// `deadline` passes first.
+    fn recv_until(&mut self, deadline: Instant) -> io::Result<Option<(Packet, usize)>> {
+        loop {
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(None);
+            }
+            self.socket.set_read_timeout(Some(deadline - now))?;
+            let len = match self.socket.recv(&mut self.buf) {
+                Ok(len) => len,
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock ||
+                              e.kind() == io::ErrorKind::TimedOut => return Ok(None),
+                Err(e) => return Err(e),
+            };
+            // A packet that cannot be decoded cannot be the reply, and `recv` would only fail
+            // on it later, so it is dropped.
+            if let Ok(packet) = Packet::decode(&self.buf[..len]) {
+                return Ok(Some((packet, len)));
+            }
+        }
+    }
+
+    fn push_unsolicited(&mut self, packet: Vec<u8>) {
+        if self.unsolicited.len() == MAX_UNSOLICITED {
+            self.unsolicited.pop_front();
+        }
+        self.unsolicited.push_back(packet);
+    }
+}
+
+fn fader_address(strip: Strip) -> io::Result<Address> {
+    Address::new(strip, Parameter::Fader)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+/// Reports a read timeout as `ErrorKind::TimedOut` on every platform, since some report it as
+/// `WouldBlock` instead.
+fn timed_out(error: io::Error) -> io::Error {
+    if error.kind() == io::ErrorKind::WouldBlock {
+        io::Error::new(io::ErrorKind::TimedOut, "no packet from the mixer")
+    } else {
+        error
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use std::thread;
+
+    use emulator;
+
+    /// Returns a client and a socket standing in for a mixer, connected to each other.
+    fn pair() -> (MixerClient, UdpSocket) {
+        let mixer = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket.connect(mixer.local_addr().unwrap()).unwrap();
+        mixer.connect(socket.local_addr().unwrap()).unwrap();
+        (MixerClient::new(socket), mixer)
+    }
+
+    fn send(mixer: &UdpSocket, command: &Command) {
+        let mut buf = Vec::new();
+        command.encode(&mut buf);
+        mixer.send(&buf).unwrap();
+    }
+
+    /// Receives requests on the mixer until none arrive for a second, returning when each
+    /// arrived.
+    fn arrivals(mixer: UdpSocket, request: Command) -> thread::JoinHandle<Vec<Instant>> {
+        thread::spawn(move || {
+            mixer.set_read_timeout(Some(Duration::from_secs(1))).unwrap();
+            let mut buf = [0; 512];
+            let mut arrivals = Vec::new();
+            while let Ok(len) = mixer.recv(&mut buf) {
+                assert_eq!(Command::decode(&buf[..len]).unwrap(), request);
+                arrivals.push(Instant::now());
+            }
+            arrivals
+        })
+    }
+
+    #[test]
+    fn queries_the_emulator() {
+        let mut client = emulator::start();
+        assert!(client.last_packet().is_empty());
+        let reply = client.query("/ch/01/mix/fader").unwrap();
+        assert_eq!(reply.address_pattern, "/ch/01/mix/fader");
+        assert_eq!(Command::decode(client.last_packet()).unwrap(), reply);
+
+        client.send(&Command::new("/ch/01/mix/fader").arg(0.25)).unwrap();
+        let reply = client.query("/ch/01/mix/fader").unwrap();
+        assert_eq!(reply, Command::new("/ch/01/mix/fader").arg(0.25));
+        assert_eq!(Command::decode(client.last_packet()).unwrap(), reply);
+        assert_eq!(client.unsolicited(), 0);
+    }
+
+    #[test]
+    fn retries_with_doubling_waits_then_times_out() {
+        let (mut client, mixer) = pair();
+        client.set_timeout(Duration::from_millis(20));
+        client.set_retries(3);
+        let listener = arrivals(mixer, Command::new("/ch/01/mix/fader"));
+
+        let start = Instant::now();
+        let error = client.query("/ch/01/mix/fader").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+        assert_eq!(error.to_string(), "no reply to /ch/01/mix/fader from the mixer");
+        // 20, 40, 80 and then 160 ms.
+        assert!(start.elapsed() >= Duration::from_millis(300));
+
+        let arrivals = listener.join().unwrap();
+        assert_eq!(arrivals.len(), 4);
+        for (i, times) in arrivals.windows(2).enumerate() {
+            // Allow for the requests taking different times to arrive.
+            assert!(times[1] - times[0] >= Duration::from_millis(15 << i));
+        }
+        assert!(client.last_packet().is_empty());
+    }
+
+    #[test]
+    fn sends_once_without_retries() {
+        let (mut client, mixer) = pair();
+        client.set_timeout(Duration::from_millis(20));
+        client.set_retries(0);
+        let listener = arrivals(mixer, Command::new("/ch/01/mix/fader"));
+        let error = client.query("/ch/01/mix/fader").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+        assert_eq!(listener.join().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn returns_a_reply_to_a_retry() {
+        let (mut client, mixer) = pair();
+        client.set_timeout(Duration::from_millis(50));
+        let reply = Command::new("/ch/01/mix/fader").arg(0.5);
+        let sent = reply.clone();
+        let mixer = thread::spawn(move || {
+            let mut buf = [0; 512];
+            // The first request is lost.
+            mixer.recv(&mut buf).unwrap();
+            mixer.recv(&mut buf).unwrap();
+            send(&mixer, &sent);
+        });
+
+        assert_eq!(client.query("/ch/01/mix/fader").unwrap(), reply);
+        mixer.join().unwrap();
+        let mut encoded = Vec::new();
+        reply.encode(&mut encoded);
+        assert_eq!(client.last_packet(), &encoded[..]);
+    }
+
+    /// Queues unsolicited packets, then a reply, on the client's socket, and queries for the
+    /// reply so that the packets before it are buffered.
+    fn buffer(client: &mut MixerClient, mixer: &UdpSocket, unsolicited: &[Command]) {
+        for command in unsolicited {
+            send(mixer, command);
+        }
+        let reply = Command::new("/ch/01/mix/fader").arg(0.5);
+        send(mixer, &reply);
+        assert_eq!(client.query("/ch/01/mix/fader").unwrap(), reply);
+    }
+
+    #[test]
+    fn returns_unsolicited_packets_in_the_order_they_arrived() {
+        let (mut client, mixer) = pair();
+        let unsolicited = [
+            Command::new("/meters/1").arg(Vec::from([0u8; 8])),
+            Command::new("/ch/02/mix/on").arg(1),
+            Command::new("/meters/1").arg(Vec::from([1u8; 8])),
+        ];
+        buffer(&mut client, &mixer, &unsolicited);
+        assert_eq!(client.unsolicited(), 3);
+
+        for command in &unsolicited {
+            assert_eq!(client.recv().unwrap(), Packet::Command(command.clone()));
+            assert_eq!(Command::decode(client.last_packet()).unwrap(), *command);
+        }
+        assert_eq!(client.unsolicited(), 0);
+    }
+
+    #[test]
+    fn delivers_buffered_meters() {
+        let (mut client, mixer) = pair();
+        let meters = Command::new("/meters/1").arg(Vec::from([0u8; 8]));
+        buffer(&mut client, &mixer, &[Command::new("/ch/02/mix/on").arg(1), meters.clone()]);
+
+        let banks = [MeterBank::new(1).unwrap()];
+        let mut subscription = client.meters(&banks).unwrap();
+        assert_eq!(subscription.next().unwrap().unwrap(), meters);
+    }
+
+    #[test]
+    fn drops_the_oldest_unsolicited_packets_beyond_the_limit() {
+        let (mut client, mixer) = pair();
+        // In batches, so that the socket's receive buffer does not overflow.
+        let commands: Vec<Command> = (0..MAX_UNSOLICITED + 44)
+            .map(|i| Command::new("/meters/1").arg(i as i32))
+            .collect();
+        for batch in commands.chunks(50) {
+            buffer(&mut client, &mixer, batch);
+        }
+        assert_eq!(client.unsolicited(), MAX_UNSOLICITED);
+
+        for command in &commands[44..] {
+            assert_eq!(client.recv().unwrap(), Packet::Command(command.clone()));
+        }
+        assert_eq!(client.unsolicited(), 0);
+    }
+}
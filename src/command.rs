@@ -0,0 +1,373 @@
+//! OSC commands (messages).
+
+use core::str;
+#[cfg(feature = "std")]
+use std::io;
+
+#[cfg(feature = "alloc")]
+use alloc::string::{String, ToString};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use decode_string;
+#[cfg(feature = "alloc")]
+use argument::Argument;
+use argument::ArgumentRef;
+#[cfg(feature = "alloc")]
+use encode::{self, padded_len, put_padding, put_string, Output};
+#[cfg(feature = "std")]
+use encode::WriteOutput;
+use error::DecodeError;
+#[cfg(feature = "alloc")]
+use error::EncodeError;
+
+/// An OSC command.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Command {
+    /// The address of the method to invoke, for example `/ch/01/mix/fader`.
+    pub address_pattern: String,
+    /// The arguments to pass to the method.
+    pub arguments: Vec<Argument>,
+}
+
+#[cfg(feature = "alloc")]
+impl Command {
+
+    /// Creates a command with no arguments.
+    pub fn new<S: Into<String>>(address_pattern: S) -> Command {
+        Command {
+            address_pattern: address_pattern.into(),
+            arguments: Vec::new(),
+        }
+    }
+
+    /// Appends an argument to the command.
+    ///
+    /// ```
+    /// use april_2018_challenge::Command;
+    ///
+    /// let command = Command::new("/ch/01/config/name").arg("Vocals");
+    /// ```
+    pub fn arg<A: Into<Argument>>(mut self, argument: A) -> Command {
+        self.arguments.push(argument.into());
+        self
+    }
+
+    /// Encodes the command to a buffer.
+    pub fn encode(&self, buf: &mut Vec<u8>) {
+        encode::to_vec(buf, |out| self.put(out))
+    }
+
+    /// Encodes the command into a fixed-size buffer, returning the number of bytes written.
+    pub fn encode_to_slice(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        encode::to_slice(self.encoded_len(), buf, |out| self.put(out))
+    }
+
+    /// Encodes the command to a writer.
+    #[cfg(feature = "std")]
+    pub fn encode_to_writer<W: io::Write + ?Sized>(&self, w: &mut W) -> io::Result<()> {
+        self.put(&mut WriteOutput(w))
+    }
+
+    /// Returns the number of bytes the encoded command takes up.
+    pub fn encoded_len(&self) -> usize {
+        let tags_len: usize = self.arguments.iter().map(Argument::tags_len).sum();
+        padded_len(self.address_pattern.len() + 1) +
+            padded_len(tags_len + 2) +
+            self.arguments.iter().map(Argument::encoded_len).sum::<usize>()
+    }
+
+    pub(crate) fn put<O: Output>(&self, out: &mut O) -> Result<(), O::Error> {
+        // Encode the address pattern.
+        put_string(&self.address_pattern, out)?;
+
+        // Encode the type tags.
+        out.put(b",")?;
+        let mut tags_len = 0;
+        for argument in &self.arguments {
+            argument.put_tags(out)?;
+            tags_len += argument.tags_len();
+        }
+        out.put(&[0])?;
+        put_padding(tags_len + 2, out)?;
+
+        // Encode the arguments.
+        for argument in &self.arguments {
+            argument.put(out)?;
+        }
+        Ok(())
+    }
+
+    /// Decodes a Command from a buffer.
+    pub fn decode(packet: &[u8]) -> Result<Command, DecodeError> {
+        Command::decode_with(packet, false)
+    }
+
+    /// Decodes a Command from a buffer, rejecting padding that contains non-zero bytes and
+    /// bytes after the last argument.
+    pub fn decode_strict(packet: &[u8]) -> Result<Command, DecodeError> {
+        Command::decode_with(packet, true)
+    }
+
+    pub(crate) fn decode_with(packet: &[u8], strict: bool) -> Result<Command, DecodeError> {
+        CommandRef::decode_with(packet, strict)?.to_owned()
+    }
+}
+
+/// A borrowed OSC command, referencing the buffer it was decoded from.
+///
+/// Decoding a `CommandRef` does not allocate: arguments are decoded lazily as the iterator
+/// returned by `arguments` is advanced.
+#[derive(Debug, Clone, Copy)]
+pub struct CommandRef<'a> {
+    address_pattern: &'a str,
+    type_tags: &'a str,
+    arguments: &'a [u8],
+    offset: usize,
+    strict: bool,
+}
+
+impl<'a> CommandRef<'a> {
+
+    /// Decodes a CommandRef from a buffer.
+    pub fn decode(packet: &'a [u8]) -> Result<CommandRef<'a>, DecodeError> {
+        CommandRef::decode_with(packet, false)
+    }
+
+    /// Decodes a CommandRef from a buffer, rejecting padding that contains non-zero bytes and,
+    /// once the arguments have all been decoded, bytes after the last one.
+    pub fn decode_strict(packet: &'a [u8]) -> Result<CommandRef<'a>, DecodeError> {
+        CommandRef::decode_with(packet, true)
+    }
+
+    fn decode_with(packet: &'a [u8], strict: bool) -> Result<CommandRef<'a>, DecodeError> {
+        let offset = |buf: &[u8]| packet.len() - buf.len();
+
+        let (address_pattern, buf) = decode_string(packet, strict)?;
+
+        let tags_offset = offset(buf);
+        let (type_tags, buf) = decode_string(buf, strict)
+            .map_err(|e| e.offset_by(tags_offset))?;
+        if !type_tags.starts_with(',') {
+            return Err(DecodeError::MissingComma { offset: tags_offset });
+        }
+
+        Ok(CommandRef {
+            address_pattern,
+            type_tags: &type_tags[1..],
+            arguments: buf,
+            offset: offset(buf),
+            strict,
+        })
+    }
+
+    /// Returns the address pattern of the command.
+    pub fn address_pattern(&self) -> &'a str {
+        self.address_pattern
+    }
+
+    /// Returns the type tags of the command's arguments, without the leading `,`.
+    pub fn type_tags(&self) -> &'a str {
+        self.type_tags
+    }
+
+    /// Returns an iterator that decodes the command's arguments.
+    pub fn arguments(&self) -> Arguments<'a> {
+        Arguments {
+            tags: self.type_tags.chars(),
+            buf: self.arguments,
+            offset: self.offset,
+            strict: self.strict,
+        }
+    }
+
+    /// Decodes all of the arguments and copies the command out of the buffer it references.
+    #[cfg(feature = "alloc")]
+    pub fn to_owned(self) -> Result<Command, DecodeError> {
+        let arguments = self.arguments()
+            .map(|a| a.map(|a| a.to_owned()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Command {
+            address_pattern: self.address_pattern.to_string(),
+            arguments,
+        })
+    }
+}
+
+/// An iterator that lazily decodes the arguments of a `CommandRef`.
+///
+/// Iteration stops after the first argument that fails to decode. In strict mode, bytes left
+/// over after the last argument are returned as a final error.
+#[derive(Debug, Clone)]
+pub struct Arguments<'a> {
+    tags: str::Chars<'a>,
+    buf: &'a [u8],
+    offset: usize,
+    strict: bool,
+}
+
+impl<'a> Iterator for Arguments<'a> {
+    type Item = Result<ArgumentRef<'a>, DecodeError>;
+
+    fn next(&mut self) -> Option<Result<ArgumentRef<'a>, DecodeError>> {
+        let type_tag = match self.tags.next() {
+            Some(type_tag) => type_tag,
+            None if self.strict && !self.buf.is_empty() => {
+                self.buf = &[];
+                return Some(Err(DecodeError::TrailingBytes { offset: self.offset }));
+            },
+            None => return None,
+        };
+        match ArgumentRef::decode(type_tag, &mut self.tags, self.buf, self.strict) {
+            Ok((argument, buf)) => {
+                self.offset += self.buf.len() - buf.len();
+                self.buf = buf;
+                Some(Ok(argument))
+            },
+            Err(e) => {
+                self.tags = "".chars();
+                Some(Err(e.offset_by(self.offset)))
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Decodes a command and all its arguments, returning the first error.
+    fn decode_error(packet: &[u8]) -> DecodeError {
+        let command = match CommandRef::decode(packet) {
+            Ok(command) => command,
+            Err(e) => return e,
+        };
+        command.arguments()
+            .find_map(Result::err)
+            .expect("the packet decoded without an error")
+    }
+
+    #[test]
+    fn truncated() {
+        assert_eq!(decode_error(b"/a\0\0,i\0\0\0\x01"),
+                   DecodeError::Truncated { offset: 8, len: 4 });
+        assert_eq!(decode_error(b"/a\0\0,if\0\0\0\0\x01\0\0"),
+                   DecodeError::Truncated { offset: 12, len: 4 });
+        assert_eq!(decode_error(b"/a\0\0,d\0\0\0\0\0\0"),
+                   DecodeError::Truncated { offset: 8, len: 8 });
+    }
+
+    #[test]
+    fn unterminated() {
+        assert_eq!(decode_error(b"/abc"), DecodeError::Unterminated { offset: 0 });
+        assert_eq!(decode_error(b"/a\0\0,i"), DecodeError::Unterminated { offset: 4 });
+        assert_eq!(decode_error(b"/a\0\0,s\0\0abcd"), DecodeError::Unterminated { offset: 8 });
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        assert_eq!(decode_error(b"/\xff\0\0,\0\0\0"), DecodeError::InvalidUtf8 { offset: 0 });
+        assert_eq!(decode_error(b"/a\0\0,\xff\0\0"), DecodeError::InvalidUtf8 { offset: 4 });
+        assert_eq!(decode_error(b"/a\0\0,is\0\0\0\0\x01\xfe\0\0\0"),
+                   DecodeError::InvalidUtf8 { offset: 12 });
+    }
+
+    #[test]
+    fn missing_comma() {
+        assert_eq!(decode_error(b"/a\0\0i\0\0\0\0\0\0\x01"),
+                   DecodeError::MissingComma { offset: 4 });
+    }
+
+    #[test]
+    fn unknown_tag() {
+        assert_eq!(decode_error(b"/a\0\0,ix\0\0\0\0\x01\0\0\0\x02"),
+                   DecodeError::UnknownTag { offset: 12, tag: 'x' });
+    }
+
+    #[test]
+    fn length_overflow() {
+        assert_eq!(decode_error(b"/a\0\0,ib\0\0\0\0\x01\0\0\0\x10ab\0\0"),
+                   DecodeError::LengthOverflow { offset: 12, len: 16 });
+    }
+
+    #[test]
+    fn unbalanced_array() {
+        assert_eq!(decode_error(b"/a\0\0,i[i\0\0\0\0\0\0\0\x01\0\0\0\x02"),
+                   DecodeError::UnbalancedArray { offset: 16 });
+        assert_eq!(decode_error(b"/a\0\0,i]\0\0\0\0\x01"),
+                   DecodeError::UnbalancedArray { offset: 12 });
+    }
+
+    #[test]
+    fn arguments_stop_after_an_error() {
+        let command = CommandRef::decode(b"/a\0\0,xi\0\0\0\0\x01").unwrap();
+        let mut arguments = command.arguments();
+        assert_eq!(arguments.next().unwrap().unwrap_err(),
+                   DecodeError::UnknownTag { offset: 8, tag: 'x' });
+        assert!(arguments.next().is_none());
+    }
+
+    #[test]
+    fn too_deep() {
+        // A datagram of about 5 KB whose type tags are `,[[[[...`.
+        let mut packet = [0; 5008];
+        packet[..2].copy_from_slice(b"/a");
+        packet[4] = b',';
+        for tag in &mut packet[5..5005] {
+            *tag = b'[';
+        }
+        assert_eq!(decode_error(&packet), DecodeError::TooDeep { offset: 5008 });
+    }
+
+    /// A 3-byte blob, padded with a zero, followed by an integer.
+    const BLOB_THEN_INT: &[u8] = b"/a\0\0,bi\0\0\0\0\x03abc\0\0\0\0\x07";
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn round_trips_a_blob_that_needs_padding() {
+        let command = Command::new("/a").arg(Argument::Binary(Vec::from(&b"abc"[..]))).arg(7);
+        let mut buf = Vec::new();
+        command.encode(&mut buf);
+        assert_eq!(buf, BLOB_THEN_INT);
+        assert_eq!(command.encoded_len(), buf.len());
+        assert_eq!(Command::decode(&buf).unwrap(), command);
+        assert_eq!(Command::decode_strict(&buf).unwrap(), command);
+    }
+
+    #[test]
+    fn strict_decoding_rejects_non_zero_padding() {
+        let mut packet = [0; 20];
+        packet.copy_from_slice(BLOB_THEN_INT);
+        packet[15] = 0xff;
+        let strict = CommandRef::decode_strict(&packet).unwrap();
+        assert_eq!(strict.arguments().find_map(Result::err),
+                   Some(DecodeError::BadPadding { offset: 15 }));
+        assert!(CommandRef::decode(&packet).unwrap().arguments().all(|a| a.is_ok()));
+
+        packet[3] = b'x';
+        assert_eq!(CommandRef::decode_strict(&packet).unwrap_err(),
+                   DecodeError::BadPadding { offset: 3 });
+        assert!(CommandRef::decode(&packet).is_ok());
+    }
+
+    #[test]
+    fn strict_decoding_rejects_trailing_bytes() {
+        let packet = b"/a\0\0,i\0\0\0\0\0\x07\0\0\0\0";
+        let strict = CommandRef::decode_strict(packet).unwrap();
+        let mut arguments = strict.arguments();
+        assert!(arguments.next().unwrap().is_ok());
+        assert_eq!(arguments.next().unwrap().unwrap_err(),
+                   DecodeError::TrailingBytes { offset: 12 });
+        assert!(arguments.next().is_none());
+        assert_eq!(CommandRef::decode(packet).unwrap().arguments().count(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn strict_decoding_of_owned_commands_rejects_trailing_bytes() {
+        let packet = b"/a\0\0,\0\0\0\0\0\0\0";
+        assert_eq!(Command::decode_strict(packet), Err(DecodeError::TrailingBytes { offset: 8 }));
+        assert_eq!(Command::decode(packet), Ok(Command::new("/a")));
+    }
+}
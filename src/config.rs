@@ -0,0 +1,258 @@
+//! Locating the mixer from command-line flags, environment variables and a config file.
+
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::path::{Path, PathBuf};
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use toml;
+
+use error::ConfigError;
+
+/// The mixer's address when none is configured.
+pub const DEFAULT_HOST: &str = "192.168.1.181";
+/// The UDP port the X-series mixers accept OSC commands on.
+pub const DEFAULT_PORT: u16 = 10024;
+/// The config file read when no other is named, if it exists.
+pub const DEFAULT_CONFIG_FILE: &str = "mixer.toml";
+
+/// The address of a mixer, and the local address to reach it from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoint {
+    /// The mixer's host name or IP address. An IPv6 address may be given with or without
+    /// brackets.
+    pub host: String,
+    /// The mixer's UDP port.
+    pub port: u16,
+    /// The local address to bind to, or `None` for any local address of the same family as the
+    /// mixer's.
+    pub bind: Option<SocketAddr>,
+}
+
+impl Endpoint {
+
+    /// Loads the endpoint from command-line arguments, environment variables and a config file,
+    /// taking each setting from the first of those that has it.
+    ///
+    /// | Setting    | Flag            | Variable       | Config file key |
+    /// |------------|-----------------|----------------|-----------------|
+    /// | host       | `--host HOST`   | `MIXER_HOST`   | `host`          |
+    /// | port       | `--port PORT`   | `MIXER_PORT`   | `port`          |
+    /// | bind       | `--bind ADDR`   | `MIXER_BIND`   | `bind`          |
+    /// | config file| `--config PATH` | `MIXER_CONFIG` |                 |
+    ///
+    /// A variable or config file key is only parsed if no flag or variable above it sets the
+    /// same setting, so an invalid value that is overridden is not an error.
+    ///
+    /// Flags may also be written as `--port=PORT`. The config file is TOML, and is read from
+    /// `mixer.toml` in the working directory if no other is named and that file exists:
+    ///
+    /// ```toml
+    /// host = "xr12.local"
+    /// port = 10024
+    /// bind = "0.0.0.0:0"
+    /// ```
+    ///
+    /// Returns the endpoint and the arguments that were not endpoint flags, in order.
+    pub fn load<I>(args: I) -> Result<(Endpoint, Vec<String>), ConfigError>
+        where I: IntoIterator<Item = String>
+    {
+        let mut settings = Settings::default();
+        let mut config_file = None;
+        let mut rest = Vec::new();
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let (flag, value) = match arg.find('=') {
+                Some(i) if arg.starts_with("--") => (&arg[..i], Some(arg[i + 1..].to_string())),
+                _ => (&arg[..], None),
+            };
+            if let "--host" | "--port" | "--bind" | "--config" = flag {
+                let value = value.or_else(|| args.next())
+                    .ok_or_else(|| ConfigError::MissingValue { flag: flag.to_string() })?;
+                match flag {
+                    "--host" => settings.host = Some(value),
+                    "--port" => settings.port = Some(parse_port(flag, &value)?),
+                    "--bind" => settings.bind = Some(parse_bind(flag, &value)?),
+                    _ => config_file = Some(PathBuf::from(value)),
+                }
+            } else {
+                rest.push(arg);
+            }
+        }
+
+        settings.fill_from_env()?;
+        let config_file = config_file.or_else(|| env::var_os("MIXER_CONFIG").map(PathBuf::from));
+        match config_file {
+            Some(path) => settings.fill_from_file(&path)?,
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                settings.fill_from_file(Path::new(DEFAULT_CONFIG_FILE))?
+            },
+            None => {},
+        }
+
+        let endpoint = Endpoint {
+            host: settings.host.unwrap_or_else(|| DEFAULT_HOST.to_string()),
+            port: settings.port.unwrap_or(DEFAULT_PORT),
+            bind: settings.bind,
+        };
+        Ok((endpoint, rest))
+    }
+
+    /// Binds a UDP socket and connects it to the mixer, trying each address the host resolves
+    /// to in turn.
+    pub fn connect(&self) -> io::Result<UdpSocket> {
+        let host = self.host.trim_start_matches('[').trim_end_matches(']');
+        let mut error = None;
+        for addr in (host, self.port).to_socket_addrs()? {
+            let bind = self.bind.unwrap_or_else(|| match addr {
+                SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+                SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+            });
+            match UdpSocket::bind(bind).and_then(|socket| socket.connect(addr).map(|()| socket)) {
+                Ok(socket) => return Ok(socket),
+                Err(e) => error = Some(e),
+            }
+        }
+        Err(error.unwrap_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "mixer host has no addresses")
+        }))
+    }
+}
+
+impl Default for Endpoint {
+    fn default() -> Endpoint {
+        Endpoint {
+            host: DEFAULT_HOST.to_string(),
+            port: DEFAULT_PORT,
+            bind: None,
+        }
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.host.contains(':') && !self.host.starts_with('[') {
+            write!(f, "[{}]:{}", self.host, self.port)
+        } else {
+            write!(f, "{}:{}", self.host, self.port)
+        }
+    }
+}
+
+/// The endpoint settings found in one place, any of which may be missing.
+#[derive(Debug, Default)]
+struct Settings {
+    host: Option<String>,
+    port: Option<u16>,
+    bind: Option<SocketAddr>,
+}
+
+impl Settings {
+
+    /// Fills in the settings missing here from environment variables, leaving the variables
+    /// for settings that are already set unread.
+    fn fill_from_env(&mut self) -> Result<(), ConfigError> {
+        let var = |name| env::var(name).ok();
+        if self.host.is_none() {
+            self.host = var("MIXER_HOST");
+        }
+        if self.port.is_none() {
+            self.port = var("MIXER_PORT").map(|port| parse_port("MIXER_PORT", &port)).transpose()?;
+        }
+        if self.bind.is_none() {
+            self.bind = var("MIXER_BIND").map(|bind| parse_bind("MIXER_BIND", &bind)).transpose()?;
+        }
+        Ok(())
+    }
+
+    /// Fills in the settings missing here from a config file, skipping the keys for settings
+    /// that are already set. Keys that are not settings are still rejected.
+    fn fill_from_file(&mut self, path: &Path) -> Result<(), ConfigError> {
+        let text = fs::read_to_string(path)
+            .map_err(|error| ConfigError::Io { path: path.to_path_buf(), error })?;
+        let table: toml::value::Table = toml::from_str(&text)
+            .map_err(|error| ConfigError::Toml { path: path.to_path_buf(), error })?;
+
+        for (key, value) in table {
+            let name = format!("{} in {}", key, path.display());
+            match (key.as_str(), value) {
+                ("host", _) if self.host.is_some() => {},
+                ("port", _) if self.port.is_some() => {},
+                ("bind", _) if self.bind.is_some() => {},
+                ("host", toml::Value::String(host)) => self.host = Some(host),
+                ("port", toml::Value::Integer(port)) if (0..=0xffff).contains(&port) => {
+                    self.port = Some(port as u16)
+                },
+                ("bind", toml::Value::String(bind)) => self.bind = Some(parse_bind(&name, &bind)?),
+                ("host", value) | ("port", value) | ("bind", value) => {
+                    return Err(invalid_value(&name, &value.to_string()));
+                },
+                _ => return Err(ConfigError::UnknownKey { path: path.to_path_buf(), key }),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_port(name: &str, value: &str) -> Result<u16, ConfigError> {
+    value.parse().map_err(|_| invalid_value(name, value))
+}
+
+fn parse_bind(name: &str, value: &str) -> Result<SocketAddr, ConfigError> {
+    value.parse().map_err(|_| invalid_value(name, value))
+}
+
+fn invalid_value(name: &str, value: &str) -> ConfigError {
+    ConfigError::InvalidValue { name: name.to_string(), value: value.to_string() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process;
+
+    fn load(args: &[&str]) -> Result<Endpoint, ConfigError> {
+        Endpoint::load(args.iter().map(|arg| arg.to_string())).map(|(endpoint, _)| endpoint)
+    }
+
+    #[test]
+    fn ignores_invalid_settings_that_are_overridden() {
+        // The only test that sets these variables, so that tests running at once do not race.
+        env::set_var("MIXER_PORT", "abc");
+        env::set_var("MIXER_BIND", "nowhere");
+        let path = env::temp_dir().join(format!("mixer-{}.toml", process::id()));
+        fs::write(&path, "host = 42\nport = -1\n").unwrap();
+        let config = path.to_str().unwrap();
+
+        match load(&["--config", config, "--port", "10023"]) {
+            Err(ConfigError::InvalidValue { name, value }) => {
+                assert_eq!((&name[..], &value[..]), ("MIXER_BIND", "nowhere"))
+            },
+            result => panic!("expected an invalid MIXER_BIND, got {:?}", result),
+        }
+
+        env::set_var("MIXER_HOST", "xr12.local");
+        let endpoint = load(&["--config", config, "--port", "10023", "--bind", "0.0.0.0:0"]);
+        assert_eq!(endpoint.unwrap(), Endpoint {
+            host: "xr12.local".to_string(),
+            port: 10023,
+            bind: Some("0.0.0.0:0".parse().unwrap()),
+        });
+
+        env::remove_var("MIXER_HOST");
+        match load(&["--config", config, "--port", "10023", "--bind", "0.0.0.0:0"]) {
+            Err(ConfigError::InvalidValue { value, .. }) => assert_eq!(value, "42"),
+            result => panic!("expected an invalid host, got {:?}", result),
+        }
+
+        env::remove_var("MIXER_PORT");
+        env::remove_var("MIXER_BIND");
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,101 @@
+//! Conversions between arguments and native Rust types.
+
+use core::convert::TryFrom;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use argument::{Argument, Midi, Rgba};
+use error::TypeError;
+use timetag::Timetag;
+
+macro_rules! impl_from {
+    ($($ty:ty => $variant:ident,)*) => {
+        $(
+            impl From<$ty> for Argument {
+                fn from(value: $ty) -> Argument {
+                    Argument::$variant(value)
+                }
+            }
+        )*
+    }
+}
+
+// There is deliberately no `From<f64>`: it would make float literals default to `f64` and
+// encode as `d`, while almost every OSC peer (the X-series mixers included) expects `f`.
+impl_from! {
+    i32 => Integer,
+    i64 => Long,
+    f32 => Float,
+    String => String,
+    Vec<u8> => Binary,
+    Timetag => Timetag,
+    char => Char,
+    Rgba => Color,
+    Midi => Midi,
+    bool => Bool,
+}
+
+impl<'a> From<&'a str> for Argument {
+    fn from(value: &'a str) -> Argument {
+        Argument::String(value.into())
+    }
+}
+
+impl<'a> From<&'a [u8]> for Argument {
+    fn from(value: &'a [u8]) -> Argument {
+        Argument::Binary(value.into())
+    }
+}
+
+macro_rules! impl_try_from {
+    ($($ty:ty => $variant:ident($tag:expr),)*) => {
+        $(
+            impl<'a> TryFrom<&'a Argument> for $ty {
+                type Error = TypeError;
+
+                fn try_from(argument: &'a Argument) -> Result<$ty, TypeError> {
+                    match *argument {
+                        Argument::$variant(value) => Ok(value),
+                        _ => Err(TypeError { expected: $tag, found: argument.tag() as char }),
+                    }
+                }
+            }
+        )*
+    }
+}
+
+impl_try_from! {
+    i32 => Integer('i'),
+    i64 => Long('h'),
+    f32 => Float('f'),
+    f64 => Double('d'),
+    Timetag => Timetag('t'),
+    char => Char('c'),
+    Rgba => Color('r'),
+    Midi => Midi('m'),
+    bool => Bool('T'),
+}
+
+impl<'a> TryFrom<&'a Argument> for &'a str {
+    type Error = TypeError;
+
+    /// Converts a string or symbol argument to a string slice.
+    fn try_from(argument: &'a Argument) -> Result<&'a str, TypeError> {
+        match *argument {
+            Argument::String(ref s) | Argument::Symbol(ref s) => Ok(s),
+            _ => Err(TypeError { expected: 's', found: argument.tag() as char }),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a Argument> for &'a [u8] {
+    type Error = TypeError;
+
+    fn try_from(argument: &'a Argument) -> Result<&'a [u8], TypeError> {
+        match *argument {
+            Argument::Binary(ref b) => Ok(b),
+            _ => Err(TypeError { expected: 'b', found: argument.tag() as char }),
+        }
+    }
+}
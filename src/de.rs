@@ -0,0 +1,326 @@
+//! Deserializing Rust values from OSC argument lists with serde.
+
+use core::slice;
+
+use byteorder::{BigEndian, ByteOrder};
+use serde::de::{self, Deserialize, DeserializeSeed, IntoDeserializer, SeqAccess, Visitor};
+
+use argument::Argument;
+use command::Command;
+use error::SerdeError;
+
+/// Deserializes a value from a list of arguments.
+///
+/// This is the inverse of [`to_arguments`](fn.to_arguments.html): the fields of a struct, or the
+/// elements of a tuple or sequence, are read from the arguments in order, and any other value
+/// is read from a single argument. Every argument must be used.
+///
+/// Integer and float arguments convert to any numeric field that can hold their value, `s` and
+/// `S` arguments can be borrowed as `&str`, and `N` reads as `None`. A time tag reads as its
+/// 64-bit NTP representation, and a color or MIDI message as its four bytes in a big-endian
+/// `u32`.
+///
+/// ```
+/// extern crate april_2018_challenge;
+/// #[macro_use]
+/// extern crate serde_derive;
+///
+/// use april_2018_challenge::{from_command, Command};
+///
+/// #[derive(Deserialize)]
+/// struct Name<'a> {
+///     name: &'a str,
+/// }
+///
+/// # fn main() {
+/// let command = Command::new("/ch/01/config/name").arg("Vocals");
+/// let name: Name = from_command(&command).unwrap();
+/// assert_eq!(name.name, "Vocals");
+/// # }
+/// ```
+pub fn from_arguments<'de, T: Deserialize<'de>>(arguments: &'de [Argument])
+                                                -> Result<T, SerdeError> {
+    T::deserialize(ArgumentsDeserializer { arguments })
+}
+
+/// Deserializes a value from the arguments of a command, as described in
+/// [`from_arguments`](fn.from_arguments.html).
+pub fn from_command<'de, T: Deserialize<'de>>(command: &'de Command) -> Result<T, SerdeError> {
+    from_arguments(&command.arguments)
+}
+
+/// Deserializes a value from a whole argument list.
+struct ArgumentsDeserializer<'de> {
+    arguments: &'de [Argument],
+}
+
+impl<'de> ArgumentsDeserializer<'de> {
+
+    /// Returns a deserializer for the only argument in the list.
+    fn single(self) -> Result<ArgumentDeserializer<'de>, SerdeError> {
+        match self.arguments {
+            [] => Err(SerdeError::MissingArgument),
+            [argument] => Ok(ArgumentDeserializer(argument)),
+            [_, rest @ ..] => Err(SerdeError::TrailingArguments { count: rest.len() }),
+        }
+    }
+}
+
+macro_rules! forward_to_single {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+                self.single()?.$method(visitor)
+            }
+        )*
+    }
+}
+
+impl<'de> de::Deserializer<'de> for ArgumentsDeserializer<'de> {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        visit_arguments(self.arguments, visitor)
+    }
+
+    forward_to_single! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_f32
+        deserialize_f64 deserialize_char deserialize_str deserialize_string deserialize_bytes
+        deserialize_byte_buf deserialize_option deserialize_unit deserialize_identifier
+    }
+
+    fn deserialize_unit_struct<V>(self,
+                                  name: &'static str,
+                                  visitor: V) -> Result<V::Value, SerdeError>
+        where V: Visitor<'de>
+    {
+        self.single()?.deserialize_unit_struct(name, visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(self,
+                                     _name: &'static str,
+                                     visitor: V) -> Result<V::Value, SerdeError>
+        where V: Visitor<'de>
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(self,
+                           name: &'static str,
+                           variants: &'static [&'static str],
+                           visitor: V) -> Result<V::Value, SerdeError>
+        where V: Visitor<'de>
+    {
+        self.single()?.deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        seq tuple tuple_struct map struct ignored_any
+    }
+}
+
+/// Deserializes a value from a single argument.
+struct ArgumentDeserializer<'de>(&'de Argument);
+
+impl<'de> de::Deserializer<'de> for ArgumentDeserializer<'de> {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match *self.0 {
+            Argument::String(ref s) | Argument::Symbol(ref s) => visitor.visit_borrowed_str(s),
+            Argument::Integer(i) => visitor.visit_i32(i),
+            Argument::Float(f) => visitor.visit_f32(f),
+            Argument::Binary(ref b) => visitor.visit_borrowed_bytes(b),
+            Argument::Long(h) => visitor.visit_i64(h),
+            Argument::Double(d) => visitor.visit_f64(d),
+            Argument::Timetag(t) => visitor.visit_u64(t.to_bits()),
+            Argument::Char(c) => visitor.visit_char(c),
+            Argument::Color(c) => {
+                visitor.visit_u32(BigEndian::read_u32(&[c.red, c.green, c.blue, c.alpha]))
+            },
+            Argument::Midi(m) => {
+                visitor.visit_u32(BigEndian::read_u32(&[m.port, m.status, m.data1, m.data2]))
+            },
+            Argument::Bool(b) => visitor.visit_bool(b),
+            Argument::Nil | Argument::Infinitum => visitor.visit_unit(),
+            Argument::Array(ref elements) => visit_arguments(elements, visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match *self.0 {
+            Argument::Nil => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(self,
+                                     _name: &'static str,
+                                     visitor: V) -> Result<V::Value, SerdeError>
+        where V: Visitor<'de>
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// Reads a unit variant from its name, or from its index.
+    fn deserialize_enum<V>(self,
+                           _name: &'static str,
+                           _variants: &'static [&'static str],
+                           visitor: V) -> Result<V::Value, SerdeError>
+        where V: Visitor<'de>
+    {
+        match *self.0 {
+            Argument::String(ref s) | Argument::Symbol(ref s) => {
+                visitor.visit_enum(s.as_str().into_deserializer())
+            },
+            Argument::Integer(i) if i >= 0 => visitor.visit_enum((i as u32).into_deserializer()),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes byte_buf unit
+        unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// Visits a list of arguments as a sequence, checking that the visitor consumes all of them.
+fn visit_arguments<'de, V: Visitor<'de>>(arguments: &'de [Argument],
+                                         visitor: V) -> Result<V::Value, SerdeError> {
+    let mut seq = ArgumentSeq(arguments.iter());
+    let value = visitor.visit_seq(&mut seq)?;
+    match seq.0.len() {
+        0 => Ok(value),
+        count => Err(SerdeError::TrailingArguments { count }),
+    }
+}
+
+/// Gives a visitor access to a list of arguments one at a time.
+struct ArgumentSeq<'de>(slice::Iter<'de, Argument>);
+
+impl<'de> SeqAccess<'de> for ArgumentSeq<'de> {
+    type Error = SerdeError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, SerdeError>
+        where T: DeserializeSeed<'de>
+    {
+        match self.0.next() {
+            Some(argument) => seed.deserialize(ArgumentDeserializer(argument)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Eq {
+        on: bool,
+        band: Band,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Band {
+        gain: f32,
+        frequency: f32,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Mode {
+        Off,
+        On,
+    }
+
+    #[test]
+    fn reads_nested_structs_from_arrays() {
+        let arguments = [
+            Argument::Bool(true),
+            Argument::Array(Vec::from([Argument::Float(-3.0), Argument::Float(1000.0)])),
+        ];
+        let eq: Eq = from_arguments(&arguments).unwrap();
+        assert_eq!(eq, Eq { on: true, band: Band { gain: -3.0, frequency: 1000.0 } });
+
+        let flat = [Argument::Bool(true), Argument::Float(-3.0), Argument::Float(1000.0)];
+        assert!(from_arguments::<Eq>(&flat).is_err());
+    }
+
+    #[test]
+    fn reads_nil_as_none() {
+        assert_eq!(from_arguments::<Option<i32>>(&[Argument::Nil]), Ok(None));
+        assert_eq!(from_arguments::<Option<i32>>(&[Argument::Integer(3)]), Ok(Some(3)));
+        let arguments = [Argument::String("a".into()), Argument::Nil];
+        assert_eq!(from_arguments::<(String, Option<f32>)>(&arguments),
+                   Ok(("a".into(), None)));
+    }
+
+    #[test]
+    fn reads_unit_variants_by_name_or_index() {
+        let from = |argument: Argument| from_arguments::<Mode>(&[argument]);
+        assert_eq!(from(Argument::String("On".into())), Ok(Mode::On));
+        assert_eq!(from(Argument::Symbol("Off".into())), Ok(Mode::Off));
+        assert_eq!(from(Argument::Integer(0)), Ok(Mode::Off));
+        assert_eq!(from(Argument::Integer(1)), Ok(Mode::On));
+        assert!(from(Argument::Integer(2)).is_err());
+        assert!(from(Argument::Integer(-1)).is_err());
+        assert!(from(Argument::String("on".into())).is_err());
+    }
+
+    #[test]
+    fn reports_missing_arguments() {
+        assert_eq!(from_arguments::<i32>(&[]), Err(SerdeError::MissingArgument));
+        let arguments = [Argument::Bool(true)];
+        assert_eq!(from_arguments::<Eq>(&arguments), Err(SerdeError::MissingArgument));
+        let arguments = [Argument::Integer(1)];
+        assert_eq!(from_arguments::<(i32, i32)>(&arguments), Err(SerdeError::MissingArgument));
+    }
+
+    #[test]
+    fn reports_trailing_arguments() {
+        let arguments = [Argument::Integer(1), Argument::Integer(2), Argument::Integer(3)];
+        assert_eq!(from_arguments::<i32>(&arguments),
+                   Err(SerdeError::TrailingArguments { count: 2 }));
+        assert_eq!(from_arguments::<(i32, i32)>(&arguments),
+                   Err(SerdeError::TrailingArguments { count: 1 }));
+
+        // Within a nested array too.
+        let arguments = [
+            Argument::Bool(true),
+            Argument::Array(Vec::from([Argument::Float(0.0), Argument::Float(1.0),
+                                       Argument::Float(2.0)])),
+        ];
+        assert_eq!(from_arguments::<Eq>(&arguments),
+                   Err(SerdeError::TrailingArguments { count: 1 }));
+    }
+
+    #[test]
+    fn widens_numbers() {
+        assert_eq!(from_arguments::<i64>(&[Argument::Integer(-5)]), Ok(-5));
+        assert_eq!(from_arguments::<u8>(&[Argument::Integer(255)]), Ok(255));
+        assert_eq!(from_arguments::<u32>(&[Argument::Long(4_000_000_000)]), Ok(4_000_000_000));
+        assert_eq!(from_arguments::<f64>(&[Argument::Float(0.5)]), Ok(0.5));
+        assert_eq!(from_arguments::<f32>(&[Argument::Integer(2)]), Ok(2.0));
+        assert_eq!(from_arguments::<f64>(&[Argument::Long(1 << 40)]), Ok((1u64 << 40) as f64));
+
+        // Only into types that can hold the value.
+        assert!(from_arguments::<u8>(&[Argument::Integer(256)]).is_err());
+        assert!(from_arguments::<u32>(&[Argument::Integer(-1)]).is_err());
+        assert!(from_arguments::<i32>(&[Argument::Long(i64::MAX)]).is_err());
+        assert!(from_arguments::<i32>(&[Argument::Float(1.0)]).is_err());
+    }
+
+    #[test]
+    fn borrows_strings() {
+        let arguments = [Argument::String("Vocals".into()), Argument::Symbol("Keys".into())];
+        let names: (&str, &str) = from_arguments(&arguments).unwrap();
+        assert_eq!(names, ("Vocals", "Keys"));
+    }
+}
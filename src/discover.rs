@@ -0,0 +1,85 @@
+//! Finding mixers on the local network.
+
+use std::io;
+use std::net::{Ipv4Addr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+use alloc::vec::Vec;
+
+use command::Command;
+use info::MixerInfo;
+
+/// Broadcasts `/xinfo` and `/info` on UDP port 10024, and returns every mixer that replies
+/// within `timeout`.
+pub fn discover(timeout: Duration) -> io::Result<Vec<MixerInfo>> {
+    discover_at((Ipv4Addr::BROADCAST, 10024), timeout)
+}
+
+/// Sends `/xinfo` and `/info` to `target`, which may be a broadcast address, and returns every
+/// mixer that replies within `timeout`.
+///
+/// Each mixer is listed once, however many replies it sends. Replies that
+/// [`MixerInfo::from_command`](struct.MixerInfo.html#method.from_command) rejects are ignored.
+///
+/// ```
+/// use std::net::UdpSocket;
+/// use std::thread;
+/// use std::time::Duration;
+///
+/// use april_2018_challenge::{discover_at, Command, Model};
+///
+/// // A stand-in for a mixer, answering on the loopback interface.
+/// let responder = UdpSocket::bind("127.0.0.1:0").unwrap();
+/// let addr = responder.local_addr().unwrap();
+/// thread::spawn(move || {
+///     let mut buf = [0; 512];
+///     let (_, from) = responder.recv_from(&mut buf).unwrap();
+///     let reply = Command::new("/xinfo")
+///         .arg("127.0.0.1").arg("XR12-0A-1B-2C").arg("XR12").arg("1.17");
+///     let mut buf = Vec::new();
+///     reply.encode(&mut buf);
+///     responder.send_to(&buf, from).unwrap();
+/// });
+///
+/// let mixers = discover_at(addr, Duration::from_millis(500)).unwrap();
+/// assert_eq!(mixers.len(), 1);
+/// assert_eq!(mixers[0].model, Model::Xr12);
+/// ```
+pub fn discover_at<A: ToSocketAddrs>(target: A, timeout: Duration) -> io::Result<Vec<MixerInfo>> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+    socket.set_broadcast(true)?;
+
+    let mut buf = Vec::new();
+    for request in &[Command::new("/xinfo"), Command::new("/info")] {
+        buf.clear();
+        request.encode(&mut buf);
+        socket.send_to(&buf, &target)?;
+    }
+
+    let deadline = Instant::now() + timeout;
+    let mut mixers: Vec<MixerInfo> = Vec::new();
+    let mut buf = [0; 1024];
+    loop {
+        let now = Instant::now();
+        if now >= deadline {
+            break;
+        }
+        socket.set_read_timeout(Some(deadline - now))?;
+        let (len, from) = match socket.recv_from(&mut buf) {
+            Ok(received) => received,
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock ||
+                          e.kind() == io::ErrorKind::TimedOut => break,
+            Err(e) => return Err(e),
+        };
+
+        let info = Command::decode(&buf[..len])
+            .ok()
+            .and_then(|command| MixerInfo::from_command(&command, from.ip()).ok());
+        if let Some(info) = info {
+            if !mixers.iter().any(|mixer| mixer.ip == info.ip) {
+                mixers.push(info);
+            }
+        }
+    }
+    Ok(mixers)
+}
@@ -0,0 +1,81 @@
+//! Routing incoming commands to handlers by address pattern.
+
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use bundle::Packet;
+use command::Command;
+use pattern::address_matches;
+
+/// A boxed command handler.
+type Handler<'a> = Box<dyn FnMut(&Command) + 'a>;
+
+/// Routes commands to the handlers registered for address patterns that match them.
+///
+/// Patterns use the syntax described in [`address_matches`](fn.address_matches.html). Every
+/// matching handler is called, in the order the handlers were added.
+///
+/// ```
+/// use april_2018_challenge::{Argument, Command, Dispatcher};
+///
+/// let mut faders = Vec::new();
+/// {
+///     let mut dispatcher = Dispatcher::new();
+///     dispatcher.add("/ch/*/mix/fader", |command: &Command| {
+///         faders.push(command.address_pattern.clone());
+///     });
+///
+///     let command = Command {
+///         address_pattern: "/ch/02/mix/fader".to_string(),
+///         arguments: vec![Argument::Float(0.5)],
+///     };
+///     assert_eq!(dispatcher.dispatch(&command), 1);
+/// }
+/// assert_eq!(faders, ["/ch/02/mix/fader"]);
+/// ```
+pub struct Dispatcher<'a> {
+    handlers: Vec<(String, Handler<'a>)>,
+}
+
+impl<'a> Dispatcher<'a> {
+
+    /// Creates a dispatcher with no handlers.
+    pub fn new() -> Dispatcher<'a> {
+        Dispatcher {
+            handlers: Vec::new(),
+        }
+    }
+
+    /// Registers a handler for commands whose address matches `pattern`.
+    pub fn add<F>(&mut self, pattern: &str, handler: F)
+        where F: FnMut(&Command) + 'a
+    {
+        self.handlers.push((pattern.to_string(), Box::new(handler)));
+    }
+
+    /// Calls every handler whose pattern matches the command's address, returning the number of
+    /// handlers called.
+    pub fn dispatch(&mut self, command: &Command) -> usize {
+        let mut called = 0;
+        for &mut (ref pattern, ref mut handler) in &mut self.handlers {
+            if address_matches(pattern, &command.address_pattern) {
+                handler(command);
+                called += 1;
+            }
+        }
+        called
+    }
+
+    /// Dispatches a packet, dispatching each command in a bundle in order. Returns the number of
+    /// handlers called.
+    pub fn dispatch_packet(&mut self, packet: &Packet) -> usize {
+        packet.commands().map(|command| self.dispatch(command)).sum()
+    }
+}
+
+impl<'a> Default for Dispatcher<'a> {
+    fn default() -> Dispatcher<'a> {
+        Dispatcher::new()
+    }
+}
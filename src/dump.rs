@@ -0,0 +1,274 @@
+//! Annotated hex dumps of encoded packets.
+
+use core::fmt;
+
+use byteorder::{BigEndian, ByteOrder};
+
+use {decode_string, split, MAX_BUNDLE_DEPTH};
+use argument::ArgumentRef;
+use error::DecodeError;
+use timetag::Timetag;
+
+/// The number of bytes shown on each line of a dump.
+const BYTES_PER_LINE: usize = 16;
+/// The width of the bytes on a line, including brackets, before the meaning is shown.
+const MEANING_COLUMN: usize = 2 * BYTES_PER_LINE + 4;
+
+/// Returns a value that prints an annotated hex dump of an encoded command or bundle.
+///
+/// Each field of the packet is printed on its own line with its offset, its raw bytes and what
+/// it decodes to. A string's null terminator and the padding after any field are shown in
+/// brackets, and flagged if they are not all zero bytes. Fields longer than 16 bytes continue
+/// on the following lines.
+///
+/// Decoding stops at the first field that is not valid, and the rest of the packet, or of the
+/// bundle element, is printed along with the error.
+///
+/// ```
+/// use april_2018_challenge::{hex_dump, Command};
+///
+/// let command = Command::new("/play/note").arg("Guitar").arg(12).arg(1.0);
+/// let mut buf = Vec::new();
+/// command.encode(&mut buf);
+///
+/// assert_eq!(hex_dump(&buf).to_string(), "\
+/// 0000  2f706c61792f6e6f7465[0000]          |-> /play/note
+/// 000c  2c736966[00000000]                  |-> ,sif
+/// 0014  477569746172[0000]                  |-> \"Guitar\"
+/// 001c  0000000c                            |-> 12
+/// 0020  3f800000                            |-> 1.0
+/// ");
+/// ```
+pub fn hex_dump(packet: &[u8]) -> HexDump<'_> {
+    HexDump { packet }
+}
+
+/// An annotated hex dump of an encoded packet, created by [`hex_dump`](fn.hex_dump.html).
+#[derive(Debug, Clone, Copy)]
+pub struct HexDump<'a> {
+    packet: &'a [u8],
+}
+
+impl<'a> fmt::Display for HexDump<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        dump_packet(f, self.packet, 0, 1)
+    }
+}
+
+/// Dumps a command or a bundle that starts `offset` bytes into the packet, where a bundle
+/// would be nested `depth` deep.
+fn dump_packet(f: &mut fmt::Formatter, buf: &[u8], offset: usize, depth: usize) -> fmt::Result {
+    if buf.starts_with(b"#bundle\0") {
+        dump_bundle(f, buf, offset, depth)
+    } else {
+        dump_command(f, buf, offset)
+    }
+}
+
+/// Dumps a command that starts `offset` bytes into the packet.
+fn dump_command(f: &mut fmt::Formatter, buf: &[u8], offset: usize) -> fmt::Result {
+    let end = offset + buf.len();
+    let offset_of = |b: &[u8]| end - b.len();
+
+    let (address_pattern, rest) = match decode_string(buf, false) {
+        Ok(decoded) => decoded,
+        Err(e) => return dump_error(f, buf, offset, e),
+    };
+    let data_len = address_pattern.len();
+    dump_field(f, &buf[..buf.len() - rest.len()], offset, data_len, &address_pattern)?;
+
+    let buf = rest;
+    let at = offset_of(buf);
+    let (type_tags, mut rest) = match decode_string(buf, false) {
+        Ok((type_tags, _)) if !type_tags.starts_with(',') => {
+            return dump_error(f, buf, at, DecodeError::MissingComma { offset: 0 });
+        },
+        Ok(decoded) => decoded,
+        Err(e) => return dump_error(f, buf, at, e),
+    };
+    dump_field(f, &buf[..buf.len() - rest.len()], at, type_tags.len(), &type_tags)?;
+
+    for tag in type_tags[1..].chars() {
+        // Array brackets and the tags that carry their value in the type tag have no bytes.
+        if let '[' | ']' | 'T' | 'F' | 'N' | 'I' = tag {
+            continue;
+        }
+
+        let buf = rest;
+        let at = offset_of(buf);
+        let (argument, next) = match ArgumentRef::decode(tag, &mut "".chars(), buf, false) {
+            Ok(decoded) => decoded,
+            Err(e) => return dump_error(f, buf, at, e),
+        };
+        let field = &buf[..buf.len() - next.len()];
+        match argument {
+            ArgumentRef::String(s) | ArgumentRef::Symbol(s) => {
+                dump_field(f, field, at, s.len(), &format_args!("{:?}", s))?
+            },
+            ArgumentRef::Binary(b) => {
+                let (size, data) = field.split_at(4);
+                dump_field(f, size, at, 4, &format_args!("blob of {} bytes", b.len()))?;
+                dump_field(f, data, at + 4, b.len(), &"blob data")?
+            },
+            ArgumentRef::Integer(i) => dump_field(f, field, at, 4, &i)?,
+            ArgumentRef::Float(x) => dump_field(f, field, at, 4, &format_args!("{:?}", x))?,
+            ArgumentRef::Long(h) => dump_field(f, field, at, 8, &h)?,
+            ArgumentRef::Double(x) => dump_field(f, field, at, 8, &format_args!("{:?}", x))?,
+            ArgumentRef::Timetag(t) => dump_field(f, field, at, 8, &TimetagMeaning(t))?,
+            ArgumentRef::Char(c) => dump_field(f, field, at, 4, &format_args!("{:?}", c))?,
+            ArgumentRef::Color(c) => dump_field(f, field, at, 4, &format_args!("{:?}", c))?,
+            ArgumentRef::Midi(m) => dump_field(f, field, at, 4, &format_args!("{:?}", m))?,
+            ArgumentRef::Bool(_) |
+            ArgumentRef::Nil |
+            ArgumentRef::Infinitum |
+            ArgumentRef::Array(_) => {},
+        }
+        rest = next;
+    }
+
+    if !rest.is_empty() {
+        dump_field(f, rest, offset_of(rest), rest.len(), &"unexpected trailing bytes")?;
+    }
+    Ok(())
+}
+
+/// Dumps a bundle nested `depth` deep that starts `offset` bytes into the packet.
+fn dump_bundle(f: &mut fmt::Formatter, buf: &[u8], offset: usize, depth: usize) -> fmt::Result {
+    let end = offset + buf.len();
+    let offset_of = |b: &[u8]| end - b.len();
+    if depth > MAX_BUNDLE_DEPTH {
+        return dump_error(f, buf, offset, DecodeError::TooDeep { offset: 0 });
+    }
+
+    let (header, rest) = buf.split_at(8);
+    dump_field(f, header, offset, 7, &"#bundle")?;
+
+    let buf = rest;
+    let (timetag, mut rest) = match split(buf, 8) {
+        Ok(split) => split,
+        Err(e) => return dump_error(f, buf, offset_of(buf), e),
+    };
+    let timetag = Timetag::from_bits(BigEndian::read_u64(timetag));
+    dump_field(f, &buf[..8], offset_of(buf), 8, &TimetagMeaning(timetag))?;
+
+    while !rest.is_empty() {
+        let buf = rest;
+        let at = offset_of(buf);
+        let (size, elements) = match split(buf, 4) {
+            Ok(split) => split,
+            Err(e) => return dump_error(f, buf, at, e),
+        };
+        let len = BigEndian::read_u32(size);
+        if len as usize > elements.len() {
+            return dump_error(f, buf, at, DecodeError::LengthOverflow { offset: 0, len });
+        }
+        if len % 4 != 0 {
+            return dump_error(f, buf, at, DecodeError::BadPadding { offset: 0 });
+        }
+        dump_field(f, size, at, 4, &format_args!("element of {} bytes", len))?;
+
+        let (element, next) = elements.split_at(len as usize);
+        dump_packet(f, element, at + 4, depth + 1)?;
+        rest = next;
+    }
+    Ok(())
+}
+
+/// Dumps the rest of a packet that failed to decode at `offset`, along with the error.
+fn dump_error(f: &mut fmt::Formatter,
+              buf: &[u8],
+              offset: usize,
+              error: DecodeError) -> fmt::Result {
+    let error = error.offset_by(offset);
+    let meaning = format_args!("error: {}", error);
+    if buf.is_empty() {
+        // There are no bytes to show, but the error still needs a line.
+        return writeln!(f, "{:04x}  {:pad$}|-> {}", offset, "", meaning, pad = MEANING_COLUMN);
+    }
+    dump_field(f, buf, offset, buf.len(), &meaning)
+}
+
+/// Prints a field's bytes and meaning, bracketing the bytes after the first `data_len`.
+fn dump_field(f: &mut fmt::Formatter,
+              field: &[u8],
+              offset: usize,
+              data_len: usize,
+              meaning: &dyn fmt::Display) -> fmt::Result {
+    let bad_padding = field.iter().skip(data_len).any(|&byte| byte != 0);
+    for (line, bytes) in field.chunks(BYTES_PER_LINE).enumerate() {
+        let start = line * BYTES_PER_LINE;
+        write!(f, "{:04x}  ", offset + start)?;
+
+        // A line that starts in the padding reopens the bracket left open on the line before.
+        let mut width = 0;
+        for (i, byte) in bytes.iter().enumerate() {
+            if start + i == data_len || (i == 0 && start > data_len) {
+                f.write_str("[")?;
+                width += 1;
+            }
+            write!(f, "{:02x}", byte)?;
+            width += 2;
+        }
+        if start + bytes.len() > data_len {
+            f.write_str("]")?;
+            width += 1;
+        }
+
+        if line == 0 {
+            let pad = MEANING_COLUMN.saturating_sub(width);
+            write!(f, "{:pad$}|-> {}", "", meaning, pad = pad)?;
+            if bad_padding {
+                f.write_str(" (non-zero padding)")?;
+            }
+        }
+        f.write_str("\n")?;
+    }
+    Ok(())
+}
+
+/// Displays a time tag as its seconds and fraction in hex, or as `immediately`.
+struct TimetagMeaning(Timetag);
+
+impl fmt::Display for TimetagMeaning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0 == Timetag::IMMEDIATELY {
+            f.write_str("immediately")
+        } else {
+            write!(f, "{:08x}.{:08x}", self.0.seconds, self.0.fraction)
+        }
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn dumps_the_error_for_an_empty_packet() {
+        assert_eq!(hex_dump(&[]).to_string(), "\
+0000                                      |-> error: string at offset 0 has no null terminator
+");
+    }
+
+    #[test]
+    fn dumps_the_error_for_an_empty_bundle_element() {
+        let packet = b"#bundle\0\0\0\0\0\0\0\0\x01\0\0\0\0";
+        assert_eq!(hex_dump(packet).to_string(), "\
+0000  2362756e646c65[00]                  |-> #bundle
+0008  0000000000000001                    |-> immediately
+0010  00000000                            |-> element of 0 bytes
+0014                                      |-> error: string at offset 20 has no null terminator
+");
+    }
+
+    #[test]
+    fn flags_non_zero_padding() {
+        let packet = b"/a\0x,s\0\0ab\0\0";
+        assert_eq!(hex_dump(packet).to_string(), "\
+0000  2f61[0078]                          |-> /a (non-zero padding)
+0004  2c73[0000]                          |-> ,s
+0008  6162[0000]                          |-> \"ab\"
+");
+    }
+}
@@ -0,0 +1,394 @@
+//! A stand-in for an XR12 mixer, for running clients without the hardware.
+
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+#[cfg(test)]
+use std::thread;
+use std::time::{Duration, Instant};
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use byteorder::{ByteOrder, LittleEndian};
+
+use address::{Address, Parameter, Strip};
+use argument::Argument;
+use bundle::Packet;
+#[cfg(test)]
+use client::MixerClient;
+use command::Command;
+
+/// The name the emulator reports when no other is set.
+pub const EMULATOR_NAME: &str = "XR12-EMULATOR";
+/// The model the emulator reports.
+const MODEL: &str = "XR12";
+/// The firmware version the emulator reports.
+const FIRMWARE: &str = "1.17";
+/// The OSC server version the emulator reports.
+const SERVER_VERSION: &str = "V0.04";
+/// How long `/xremote` and `/meters` subscriptions last.
+const SUBSCRIPTION_TIME: Duration = Duration::from_secs(10);
+/// How often meters are sent for each unit of a subscription's time factor.
+const METERS_TICK: Duration = Duration::from_millis(50);
+/// The number of values in every bank of meters.
+const METERS_PER_BANK: usize = 40;
+/// How long to wait for a packet when no meters are due.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(1);
+/// The number of input channels on an XR12.
+const CHANNELS: u8 = 12;
+/// The number of mix buses on an XR12.
+const BUSES: u8 = 2;
+
+/// A stand-in for an XR12 mixer that answers OSC commands over UDP.
+///
+/// The emulator:
+///
+/// * answers `/info` and `/xinfo` with its name, model and firmware version;
+/// * stores the arguments of any other command that has them, and answers the same address
+///   with no arguments with the stored values, as a mixer does for its parameters;
+/// * sends each change to the other clients that have sent `/xremote` in the last 10 seconds;
+/// * sends synthetic meters to clients that have sent `/meters ,s /meters/N` in the last 10
+///   seconds, every 50 ms times the last integer argument, if there is one;
+/// * cancels a client's subscriptions when it sends `/unsubscribe`.
+///
+/// The channel settings covered by [`ChannelStrip`](struct.ChannelStrip.html), the EQs of the
+/// channels, buses and main mix, and the main fader, start out with values; other parameters
+/// have none until they are set. Commands it cannot decode are ignored, as are errors sending
+/// to a client, so that one unreachable client does not stop the others being served.
+///
+/// ```
+/// use std::net::UdpSocket;
+/// use std::thread;
+///
+/// use april_2018_challenge::{Command, Emulator, MixerClient};
+///
+/// let mut emulator = Emulator::bind("127.0.0.1:0").unwrap();
+/// let addr = emulator.local_addr().unwrap();
+/// thread::spawn(move || emulator.run());
+///
+/// let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+/// socket.connect(addr).unwrap();
+/// let mut client = MixerClient::new(socket);
+/// client.send(&Command::new("/ch/01/mix/fader").arg(0.75)).unwrap();
+/// let fader = client.query("/ch/01/mix/fader").unwrap();
+/// assert_eq!(fader, Command::new("/ch/01/mix/fader").arg(0.75));
+/// ```
+#[derive(Debug)]
+pub struct Emulator {
+    socket: UdpSocket,
+    name: String,
+    /// The stored arguments of each parameter, by address.
+    parameters: HashMap<String, Vec<Argument>>,
+    /// The clients subscribed with `/xremote`, and when their subscriptions end.
+    remotes: HashMap<SocketAddr, Instant>,
+    meters: Vec<MeterSubscription>,
+    /// When the emulator was created, which the synthetic meter levels are timed from.
+    started: Instant,
+    buf: Vec<u8>,
+}
+
+/// A client's subscription to a bank of meters.
+#[derive(Debug)]
+struct MeterSubscription {
+    client: SocketAddr,
+    bank: u8,
+    interval: Duration,
+    /// When the subscription ends, unless it is renewed.
+    expires: Instant,
+    /// When the meters are next due.
+    next: Instant,
+}
+
+impl Emulator {
+
+    /// Binds the emulator to a UDP address, such as `127.0.0.1:10024`.
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<Emulator> {
+        let mut emulator = Emulator {
+            socket: UdpSocket::bind(addr)?,
+            name: EMULATOR_NAME.to_string(),
+            parameters: HashMap::new(),
+            remotes: HashMap::new(),
+            meters: Vec::new(),
+            started: Instant::now(),
+            buf: Vec::new(),
+        };
+        emulator.buf.resize(65536, 0);
+        for channel in 1..=CHANNELS {
+            let strip = Strip::Channel(channel);
+            emulator.set_parameter(strip, Parameter::Fader, 0.0.into());
+            emulator.set_parameter(strip, Parameter::On, 1.into());
+            emulator.set_parameter(strip, Parameter::Pan, 0.5.into());
+            emulator.set_parameter(strip, Parameter::Name, "".into());
+            emulator.set_parameter(strip, Parameter::Color, 0.into());
+            emulator.set_parameter(strip, Parameter::Gain, (1.0 / 6.0).into());
+            emulator.set_parameter(strip, Parameter::Phantom, 0.into());
+            emulator.set_parameter(strip, Parameter::LowCut, 0.into());
+            emulator.set_parameter(strip, Parameter::LowCutFrequency, 0.0.into());
+            emulator.set_parameter(strip, Parameter::Invert, 0.into());
+            emulator.set_eq(strip);
+        }
+        for bus in 1..=BUSES {
+            emulator.set_eq(Strip::Bus(bus));
+        }
+        emulator.set_parameter(Strip::Main, Parameter::Fader, 0.75.into());
+        emulator.set_eq(Strip::Main);
+        Ok(emulator)
+    }
+
+    /// Returns the address the emulator is bound to.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Sets the name the emulator reports in reply to `/info` and `/xinfo`.
+    pub fn set_name<S: Into<String>>(&mut self, name: S) {
+        self.name = name.into();
+    }
+
+    /// Returns the stored arguments of the parameter at `address`.
+    pub fn get(&self, address: &str) -> Option<&[Argument]> {
+        self.parameters.get(address).map(|arguments| &arguments[..])
+    }
+
+    /// Stores the arguments of the parameter at `address`, without telling any client.
+    pub fn set(&mut self, address: &str, arguments: Vec<Argument>) {
+        self.parameters.insert(address.to_string(), arguments);
+    }
+
+    /// Stores the single argument of a strip's parameter.
+    fn set_parameter(&mut self, strip: Strip, parameter: Parameter, value: Argument) {
+        // The strips and parameters set up by `bind` all exist.
+        let address = Address::new(strip, parameter).expect("invalid parameter address");
+        self.set(&address.to_string(), Vec::from([value]));
+    }
+
+    /// Stores a flat EQ for a strip, bypassed, with parametric bands spread across the range.
+    fn set_eq(&mut self, strip: Strip) {
+        let bands = strip.eq_bands();
+        self.set_parameter(strip, Parameter::EqOn, 0.into());
+        for band in 1..=bands {
+            let frequency = band as f32 / (bands + 1) as f32;
+            self.set_parameter(strip, Parameter::EqType(band), 2.into());
+            self.set_parameter(strip, Parameter::EqFrequency(band), frequency.into());
+            self.set_parameter(strip, Parameter::EqGain(band), 0.5.into());
+            self.set_parameter(strip, Parameter::EqQ(band), 0.5.into());
+        }
+    }
+
+    /// Answers commands and sends meters until an error occurs on the socket itself.
+    pub fn run(&mut self) -> io::Result<()> {
+        loop {
+            self.poll()?;
+        }
+    }
+
+    /// Waits for a command or for meters to be due, whichever comes first, and deals with it.
+    pub fn poll(&mut self) -> io::Result<()> {
+        let now = Instant::now();
+        let timeout = self.meters.iter()
+            .map(|meters| meters.next.saturating_duration_since(now))
+            .min()
+            .unwrap_or(IDLE_TIMEOUT);
+        self.socket.set_read_timeout(Some(timeout.max(Duration::from_millis(1))))?;
+
+        match self.socket.recv_from(&mut self.buf) {
+            Ok((len, from)) => {
+                if let Ok(packet) = Packet::decode(&self.buf[..len]) {
+                    self.handle_packet(packet, from)?;
+                }
+            },
+            // Some platforms report an earlier send to a client that has gone away here.
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock ||
+                          e.kind() == io::ErrorKind::TimedOut ||
+                          e.kind() == io::ErrorKind::ConnectionReset ||
+                          e.kind() == io::ErrorKind::ConnectionRefused => {},
+            Err(e) => return Err(e),
+        }
+        self.send_meters();
+        Ok(())
+    }
+
+    fn handle_packet(&mut self, packet: Packet, from: SocketAddr) -> io::Result<()> {
+        match packet {
+            Packet::Command(command) => self.handle(command, from),
+            Packet::Bundle(bundle) => {
+                for element in bundle.elements {
+                    self.handle_packet(element, from)?;
+                }
+                Ok(())
+            },
+        }
+    }
+
+    fn handle(&mut self, command: Command, from: SocketAddr) -> io::Result<()> {
+        match command.address_pattern.as_str() {
+            "/info" => {
+                let reply = Command::new("/info")
+                    .arg(SERVER_VERSION)
+                    .arg(self.name.as_str())
+                    .arg(MODEL)
+                    .arg(FIRMWARE);
+                self.send(&reply, from);
+                Ok(())
+            },
+            "/xinfo" => {
+                let ip = self.socket.local_addr()?.ip().to_string();
+                let reply = Command::new("/xinfo")
+                    .arg(ip)
+                    .arg(self.name.as_str())
+                    .arg(MODEL)
+                    .arg(FIRMWARE);
+                self.send(&reply, from);
+                Ok(())
+            },
+            "/xremote" => {
+                self.remotes.insert(from, Instant::now() + SUBSCRIPTION_TIME);
+                Ok(())
+            },
+            "/meters" => {
+                self.subscribe_meters(&command, from);
+                Ok(())
+            },
+            "/unsubscribe" => {
+                self.remotes.remove(&from);
+                self.meters.retain(|meters| meters.client != from);
+                Ok(())
+            },
+            _ if command.arguments.is_empty() => {
+                if let Some(arguments) = self.parameters.get(&command.address_pattern) {
+                    let reply = Command {
+                        address_pattern: command.address_pattern.clone(),
+                        arguments: arguments.clone(),
+                    };
+                    self.send(&reply, from);
+                }
+                Ok(())
+            },
+            _ => {
+                self.notify_remotes(&command, from);
+                self.parameters.insert(command.address_pattern, command.arguments);
+                Ok(())
+            },
+        }
+    }
+
+    /// Starts or renews a subscription to the bank of meters named in a `/meters` command.
+    fn subscribe_meters(&mut self, command: &Command, from: SocketAddr) {
+        let bank = match command.arguments.first() {
+            Some(Argument::String(address)) => address.trim_start_matches("/meters/").parse(),
+            _ => return,
+        };
+        let bank: u8 = match bank {
+            Ok(bank) if bank <= 16 => bank,
+            _ => return,
+        };
+        let factor = match command.arguments.last() {
+            Some(&Argument::Integer(factor)) if factor > 0 => factor as u32,
+            _ => 1,
+        };
+
+        let now = Instant::now();
+        self.meters.retain(|meters| meters.client != from || meters.bank != bank);
+        self.meters.push(MeterSubscription {
+            client: from,
+            bank,
+            interval: METERS_TICK * factor,
+            expires: now + SUBSCRIPTION_TIME,
+            next: now,
+        });
+    }
+
+    /// Sends a change to every other client subscribed with `/xremote`.
+    fn notify_remotes(&mut self, command: &Command, from: SocketAddr) {
+        let now = Instant::now();
+        self.remotes.retain(|_, &mut expires| expires > now);
+        let clients: Vec<SocketAddr> = self.remotes.keys()
+            .cloned()
+            .filter(|&client| client != from)
+            .collect();
+        for client in clients {
+            self.send(command, client);
+        }
+    }
+
+    /// Sends the meters that are due, and drops the subscriptions that have run out.
+    fn send_meters(&mut self) {
+        let now = Instant::now();
+        self.meters.retain(|meters| meters.expires > now);
+
+        let seconds = (now - self.started).as_secs_f64();
+        let mut due = Vec::new();
+        for meters in &mut self.meters {
+            if meters.next <= now {
+                due.push((meters.client, meters.bank));
+                meters.next = now + meters.interval;
+            }
+        }
+        for (client, bank) in due {
+            let command = Command::new(format!("/meters/{}", bank)).arg(meter_blob(seconds));
+            self.send(&command, client);
+        }
+    }
+
+    /// Sends a command to a client, ignoring any error. A client that cannot be reached has its
+    /// subscriptions run out like any other that stops renewing them.
+    fn send(&self, command: &Command, to: SocketAddr) {
+        let mut packet = Vec::new();
+        command.encode(&mut packet);
+        let _ = self.socket.send_to(&packet, to);
+    }
+}
+
+/// Starts an emulator on a background thread, and returns a client connected to it.
+#[cfg(test)]
+pub(crate) fn start() -> MixerClient {
+    let mut emulator = Emulator::bind("127.0.0.1:0").unwrap();
+    let addr = emulator.local_addr().unwrap();
+    thread::spawn(move || emulator.run());
+
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket.connect(addr).unwrap();
+    MixerClient::new(socket)
+}
+
+/// Returns a bank of meters as the mixer sends them: the number of values as a little-endian
+/// 32-bit integer, then each value as a little-endian 16-bit integer in 1/256 dB.
+///
+/// Each value swings slowly between -48 dB and -6 dB, out of step with its neighbors.
+fn meter_blob(seconds: f64) -> Vec<u8> {
+    let mut blob = ::alloc::vec![0; 4 + 2 * METERS_PER_BANK];
+    LittleEndian::write_u32(&mut blob[..4], METERS_PER_BANK as u32);
+    for (i, value) in blob[4..].chunks_mut(2).enumerate() {
+        let phase = seconds * PI + i as f64 * 0.7;
+        let db = -27.0 + 21.0 * phase.sin();
+        LittleEndian::write_i16(value, (db * 256.0) as i16);
+    }
+    blob
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_serving_when_a_client_cannot_be_reached() {
+        let mut emulator = Emulator::bind("127.0.0.1:0").unwrap();
+        let addr = emulator.local_addr().unwrap();
+
+        // A socket bound to an IPv4 address cannot send to an IPv6 one, on any platform.
+        let unreachable = "[::1]:10024".parse().unwrap();
+        assert!(emulator.socket.send_to(b"/a\0\0", unreachable).is_err());
+        emulator.remotes.insert(unreachable, Instant::now() + SUBSCRIPTION_TIME);
+        emulator.subscribe_meters(&Command::new("/meters").arg("/meters/1"), unreachable);
+
+        let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+        client.connect(addr).unwrap();
+        let mut packet = Vec::new();
+        Command::new("/ch/01/mix/fader").arg(0.75).encode(&mut packet);
+        client.send(&packet).unwrap();
+        emulator.poll().unwrap();
+        assert_eq!(emulator.get("/ch/01/mix/fader"), Some(&[Argument::Float(0.75)][..]));
+    }
+}
@@ -0,0 +1,99 @@
+//! Encoding OSC packets into growable buffers, fixed-size buffers and writers.
+
+use core::convert::Infallible;
+#[cfg(feature = "std")]
+use std::io;
+
+use alloc::vec::Vec;
+
+use error::EncodeError;
+
+/// A destination for encoded bytes.
+pub(crate) trait Output {
+    type Error;
+
+    /// Writes all of the bytes to the output.
+    fn put(&mut self, bytes: &[u8]) -> Result<(), Self::Error>;
+}
+
+impl Output for Vec<u8> {
+    type Error = Infallible;
+
+    fn put(&mut self, bytes: &[u8]) -> Result<(), Infallible> {
+        self.extend_from_slice(bytes);
+        Ok(())
+    }
+}
+
+/// Writes into a fixed-size buffer, keeping track of how much has been written.
+pub(crate) struct SliceOutput<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> Output for SliceOutput<'a> {
+    type Error = EncodeError;
+
+    fn put(&mut self, bytes: &[u8]) -> Result<(), EncodeError> {
+        let end = self.len + bytes.len();
+        if end > self.buf.len() {
+            return Err(EncodeError::BufferTooSmall { needed: end, available: self.buf.len() });
+        }
+        self.buf[self.len..end].copy_from_slice(bytes);
+        self.len = end;
+        Ok(())
+    }
+}
+
+/// Adapts an `io::Write` to an `Output`.
+#[cfg(feature = "std")]
+pub(crate) struct WriteOutput<'a, W: io::Write + ?Sized + 'a>(pub &'a mut W);
+
+#[cfg(feature = "std")]
+impl<'a, W: io::Write + ?Sized> Output for WriteOutput<'a, W> {
+    type Error = io::Error;
+
+    fn put(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.0.write_all(bytes)
+    }
+}
+
+/// Returns `len` rounded up to a multiple of 4.
+pub(crate) fn padded_len(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+/// Writes the null bytes needed to pad a field of `len` bytes to be 4-byte aligned.
+pub(crate) fn put_padding<O: Output>(len: usize, out: &mut O) -> Result<(), O::Error> {
+    let zeros: &[u8] = &[0; 3];
+    out.put(&zeros[..padded_len(len) - len])
+}
+
+/// Writes the provided string to the output.
+pub(crate) fn put_string<O: Output>(s: &str, out: &mut O) -> Result<(), O::Error> {
+    out.put(s.as_bytes())?;
+    out.put(&[0])?;
+    put_padding(s.len() + 1, out)
+}
+
+/// Runs an encoder that cannot fail against a growable buffer.
+pub(crate) fn to_vec<F>(buf: &mut Vec<u8>, encode: F)
+    where F: FnOnce(&mut Vec<u8>) -> Result<(), Infallible>
+{
+    if let Err(e) = encode(buf) {
+        match e {}
+    }
+}
+
+/// Runs an encoder producing `len` bytes against a fixed-size buffer, returning the number of
+/// bytes written.
+pub(crate) fn to_slice<F>(len: usize, buf: &mut [u8], encode: F) -> Result<usize, EncodeError>
+    where F: FnOnce(&mut SliceOutput) -> Result<(), EncodeError>
+{
+    if len > buf.len() {
+        return Err(EncodeError::BufferTooSmall { needed: len, available: buf.len() });
+    }
+    let mut out = SliceOutput { buf, len: 0 };
+    encode(&mut out)?;
+    Ok(out.len)
+}
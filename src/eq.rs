@@ -0,0 +1,398 @@
+//! Reading and changing a strip's parametric EQ.
+
+use std::io;
+
+use alloc::format;
+use alloc::vec::Vec;
+
+use address::{Address, Parameter, Strip};
+use argument::Argument;
+use client::MixerClient;
+use setting::{bool_argument, clamp_unit, from_log_scale, get, get_bool, get_float, to_log_scale,
+              unexpected};
+
+/// The lowest frequency of an EQ band, in Hz.
+pub const MIN_EQ_HZ: f32 = 20.0;
+/// The highest frequency of an EQ band, in Hz.
+pub const MAX_EQ_HZ: f32 = 20000.0;
+/// The most an EQ band can boost or cut, in dB.
+pub const MAX_EQ_GAIN_DB: f32 = 15.0;
+/// The narrowest Q of an EQ band.
+pub const MAX_EQ_Q: f32 = 10.0;
+/// The widest Q of an EQ band.
+pub const MIN_EQ_Q: f32 = 0.3;
+
+/// The number of frequencies an EQ band can be set to.
+const FREQUENCY_STEPS: u32 = 201;
+/// The number of gains an EQ band can be set to, 0.25 dB apart.
+const GAIN_STEPS: u32 = 121;
+/// The number of Qs an EQ band can be set to.
+const Q_STEPS: u32 = 72;
+
+/// The shape of an EQ band.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EqType {
+    /// Cuts below the frequency, ignoring the gain.
+    LowCut,
+    /// Boosts or cuts below the frequency.
+    LowShelf,
+    /// Boosts or cuts around the frequency.
+    Parametric,
+    /// Boosts or cuts around the frequency, with a Q that narrows as the gain grows.
+    Vintage,
+    /// Boosts or cuts above the frequency.
+    HighShelf,
+    /// Cuts above the frequency, ignoring the gain.
+    HighCut,
+}
+
+/// Every type, in the order of the numbers the mixer uses for them.
+const EQ_TYPES: [EqType; 6] = [
+    EqType::LowCut,
+    EqType::LowShelf,
+    EqType::Parametric,
+    EqType::Vintage,
+    EqType::HighShelf,
+    EqType::HighCut,
+];
+
+impl EqType {
+
+    /// Returns the type with the number the mixer uses for it, from 0 to 5.
+    pub fn from_index(index: i32) -> Option<EqType> {
+        if index < 0 {
+            return None;
+        }
+        EQ_TYPES.get(index as usize).cloned()
+    }
+
+    /// Returns the number the mixer uses for the type, from 0 to 5.
+    pub fn index(self) -> i32 {
+        self as i32
+    }
+}
+
+/// The settings of one EQ band, in the units the mixer shows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EqBand {
+    pub eq_type: EqType,
+    /// The frequency, from 20 Hz to 20 kHz.
+    pub frequency: f32,
+    /// The gain, from -15 dB to +15 dB.
+    pub gain: f32,
+    /// The Q, from 10 (narrow) down to 0.3 (wide).
+    pub q: f32,
+}
+
+/// The parametric EQ of a channel, bus or the main mix, read and changed through a
+/// [`MixerClient`](struct.MixerClient.html). Created by
+/// [`MixerClient::eq`](struct.MixerClient.html#method.eq) or
+/// [`ChannelStrip::eq`](struct.ChannelStrip.html#method.eq).
+///
+/// Channels have 4 bands and buses and the main mix 6, numbered from 1; using any other band
+/// fails with `ErrorKind::InvalidInput`. Frequencies, gains and Qs are converted to and from
+/// the mixer's values from 0.0 to 1.0, clamped to their ranges and rounded to the nearest
+/// setting the mixer has, so they may read back slightly differently. Reading fails with
+/// `ErrorKind::InvalidData` if the reply does not hold the type of value expected.
+///
+/// ```
+/// use std::net::UdpSocket;
+/// use std::thread;
+///
+/// use april_2018_challenge::{Emulator, EqBand, EqType, MixerClient, Strip};
+///
+/// let mut emulator = Emulator::bind("127.0.0.1:0").unwrap();
+/// let addr = emulator.local_addr().unwrap();
+/// thread::spawn(move || emulator.run());
+///
+/// let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+/// socket.connect(addr).unwrap();
+/// let mut client = MixerClient::new(socket);
+/// let mut eq = client.eq(Strip::Channel(1)).unwrap();
+/// eq.set_on(true).unwrap();
+/// eq.set_band(2, &EqBand {
+///     eq_type: EqType::Parametric,
+///     frequency: 1000.0,
+///     gain: 3.0,
+///     q: 2.0,
+/// }).unwrap();
+///
+/// let curve = eq.curve().unwrap();
+/// assert_eq!(curve.len(), 4);
+/// assert_eq!(curve[1].eq_type, EqType::Parametric);
+/// assert!((curve[1].frequency - 1000.0).abs() < 20.0);
+/// assert_eq!(curve[1].gain, 3.0);
+/// assert!((curve[1].q - 2.0).abs() < 0.05);
+/// assert!(eq.set_gain(5, 0.0).is_err());
+/// ```
+#[derive(Debug)]
+pub struct Equalizer<'a> {
+    client: &'a mut MixerClient,
+    strip: Strip,
+}
+
+impl<'a> Equalizer<'a> {
+
+    pub(crate) fn new(client: &'a mut MixerClient, strip: Strip) -> io::Result<Equalizer<'a>> {
+        let strip = strip.validate()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        if strip.eq_bands() == 0 {
+            let message = format!("{} has no EQ", strip);
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+        }
+        Ok(Equalizer { client, strip })
+    }
+
+    /// Returns the strip the EQ belongs to.
+    pub fn strip(&self) -> Strip {
+        self.strip
+    }
+
+    /// Returns the number of bands, 4 on a channel and 6 on a bus or the main mix.
+    pub fn bands(&self) -> u8 {
+        self.strip.eq_bands()
+    }
+
+    /// Returns whether the EQ is on.
+    pub fn on(&mut self) -> io::Result<bool> {
+        let address = self.address(Parameter::EqOn)?;
+        get_bool(self.client, address)
+    }
+
+    /// Turns the EQ on, or bypasses it.
+    pub fn set_on(&mut self, on: bool) -> io::Result<()> {
+        self.set(Parameter::EqOn, bool_argument(on))
+    }
+
+    /// Returns the type of a band.
+    pub fn eq_type(&mut self, band: u8) -> io::Result<EqType> {
+        let address = self.address(Parameter::EqType(band))?;
+        match get(self.client, address)? {
+            Argument::Integer(index) => {
+                EqType::from_index(index)
+                    .ok_or_else(|| unexpected(address, &Argument::Integer(index)))
+            },
+            argument => Err(unexpected(address, &argument)),
+        }
+    }
+
+    /// Sets the type of a band.
+    pub fn set_eq_type(&mut self, band: u8, eq_type: EqType) -> io::Result<()> {
+        self.set(Parameter::EqType(band), eq_type.index().into())
+    }
+
+    /// Returns the frequency of a band, from 20 Hz to 20 kHz.
+    pub fn frequency(&mut self, band: u8) -> io::Result<f32> {
+        self.get_float(Parameter::EqFrequency(band))
+            .map(|value| from_log_scale(value, MIN_EQ_HZ, MAX_EQ_HZ))
+    }
+
+    /// Sets the frequency of a band, from 20 Hz to 20 kHz.
+    pub fn set_frequency(&mut self, band: u8, hz: f32) -> io::Result<()> {
+        let value = quantize(to_log_scale(hz, MIN_EQ_HZ, MAX_EQ_HZ), FREQUENCY_STEPS);
+        self.set(Parameter::EqFrequency(band), value.into())
+    }
+
+    /// Returns the gain of a band, from -15 dB to +15 dB.
+    pub fn gain(&mut self, band: u8) -> io::Result<f32> {
+        // Counting in steps of 0.25 dB keeps the gain exact, as the mixer shows it.
+        self.get_float(Parameter::EqGain(band))
+            .map(|value| (clamp_unit(value) * (GAIN_STEPS - 1) as f32).round() * 0.25)
+            .map(|db| db - MAX_EQ_GAIN_DB)
+    }
+
+    /// Sets the gain of a band, from -15 dB to +15 dB.
+    pub fn set_gain(&mut self, band: u8, db: f32) -> io::Result<()> {
+        let value = quantize((db / MAX_EQ_GAIN_DB + 1.0) / 2.0, GAIN_STEPS);
+        self.set(Parameter::EqGain(band), value.into())
+    }
+
+    /// Returns the Q of a band, from 10 (narrow) down to 0.3 (wide).
+    pub fn q(&mut self, band: u8) -> io::Result<f32> {
+        self.get_float(Parameter::EqQ(band))
+            .map(|value| from_log_scale(value, MAX_EQ_Q, MIN_EQ_Q))
+    }
+
+    /// Sets the Q of a band, from 10 (narrow) down to 0.3 (wide).
+    pub fn set_q(&mut self, band: u8, q: f32) -> io::Result<()> {
+        let value = quantize(to_log_scale(q, MAX_EQ_Q, MIN_EQ_Q), Q_STEPS);
+        self.set(Parameter::EqQ(band), value.into())
+    }
+
+    /// Returns all the settings of a band.
+    pub fn band(&mut self, band: u8) -> io::Result<EqBand> {
+        Ok(EqBand {
+            eq_type: self.eq_type(band)?,
+            frequency: self.frequency(band)?,
+            gain: self.gain(band)?,
+            q: self.q(band)?,
+        })
+    }
+
+    /// Changes all the settings of a band.
+    pub fn set_band(&mut self, band: u8, settings: &EqBand) -> io::Result<()> {
+        self.set_eq_type(band, settings.eq_type)?;
+        self.set_frequency(band, settings.frequency)?;
+        self.set_gain(band, settings.gain)?;
+        self.set_q(band, settings.q)
+    }
+
+    /// Returns the settings of every band, in order, to draw the EQ's curve.
+    pub fn curve(&mut self) -> io::Result<Vec<EqBand>> {
+        (1..=self.bands()).map(|band| self.band(band)).collect()
+    }
+
+    /// Returns the address of one of the EQ's parameters, failing with
+    /// `ErrorKind::InvalidInput` if the band does not exist.
+    fn address(&self, parameter: Parameter) -> io::Result<Address> {
+        Address::new(self.strip, parameter)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+    }
+
+    fn get_float(&mut self, parameter: Parameter) -> io::Result<f32> {
+        let address = self.address(parameter)?;
+        get_float(self.client, address)
+    }
+
+    fn set(&mut self, parameter: Parameter, value: Argument) -> io::Result<()> {
+        let command = self.address(parameter)?.set(value);
+        self.client.send(&command)
+    }
+}
+
+/// Rounds a value from 0.0 to 1.0 to the nearest of `steps` evenly spaced settings.
+fn quantize(value: f32, steps: u32) -> f32 {
+    let max = (steps - 1) as f32;
+    (clamp_unit(value) * max).round() / max
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use emulator;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-3, "{} is not close to {}", actual, expected);
+    }
+
+    /// Checks that a value from 0.0 to 1.0 is one of `steps` evenly spaced settings.
+    fn assert_quantized(value: f32, steps: u32) {
+        let max = (steps - 1) as f32;
+        assert_close(value * max, (value * max).round());
+    }
+
+    #[test]
+    fn scales_the_frequency() {
+        let mut client = emulator::start();
+        let mut eq = client.eq(Strip::Channel(1)).unwrap();
+        for &(hz, value) in &[(20.0, 0.0), (20000.0, 1.0), (10.0, 0.0), (0.0, 0.0),
+                              (-5.0, 0.0), (30000.0, 1.0), (f32::NAN, 0.0)] {
+            eq.set_frequency(1, hz).unwrap();
+            assert_close(eq.get_float(Parameter::EqFrequency(1)).unwrap(), value);
+        }
+        // Halfway along the log scale is the geometric mean of the ends.
+        eq.set(Parameter::EqFrequency(1), 0.5.into()).unwrap();
+        assert_close(eq.frequency(1).unwrap(), (20.0f32 * 20000.0).sqrt());
+
+        eq.set_frequency(1, 1234.0).unwrap();
+        assert_quantized(eq.get_float(Parameter::EqFrequency(1)).unwrap(), FREQUENCY_STEPS);
+        assert!((eq.frequency(1).unwrap() - 1234.0).abs() < 1234.0 * 0.02);
+    }
+
+    #[test]
+    fn scales_the_gain() {
+        let mut client = emulator::start();
+        let mut eq = client.eq(Strip::Channel(2)).unwrap();
+        for &(db, value) in &[(-15.0, 0.0), (0.0, 0.5), (15.0, 1.0), (-20.0, 0.0), (20.0, 1.0)] {
+            eq.set_gain(1, db).unwrap();
+            assert_close(eq.get_float(Parameter::EqGain(1)).unwrap(), value);
+        }
+        eq.set(Parameter::EqGain(1), 0.25.into()).unwrap();
+        assert_eq!(eq.gain(1).unwrap(), -7.5);
+
+        // Gains are rounded to the nearest 0.25 dB, and read back exactly.
+        for &(db, rounded) in &[(3.1, 3.0), (3.13, 3.25), (-0.1, 0.0), (14.9, 15.0)] {
+            eq.set_gain(1, db).unwrap();
+            assert_quantized(eq.get_float(Parameter::EqGain(1)).unwrap(), GAIN_STEPS);
+            assert_eq!(eq.gain(1).unwrap(), rounded);
+        }
+    }
+
+    #[test]
+    fn scales_the_q_from_narrow_to_wide() {
+        let mut client = emulator::start();
+        let mut eq = client.eq(Strip::Channel(3)).unwrap();
+        // The scale runs from the narrowest Q at 0.0 to the widest at 1.0.
+        for &(q, value) in &[(MAX_EQ_Q, 0.0), (MIN_EQ_Q, 1.0), (20.0, 0.0), (0.1, 1.0),
+                             (0.0, 1.0), (-1.0, 1.0)] {
+            eq.set_q(1, q).unwrap();
+            assert_close(eq.get_float(Parameter::EqQ(1)).unwrap(), value);
+        }
+        eq.set(Parameter::EqQ(1), 0.0.into()).unwrap();
+        assert_close(eq.q(1).unwrap(), MAX_EQ_Q);
+        eq.set(Parameter::EqQ(1), 0.5.into()).unwrap();
+        assert_close(eq.q(1).unwrap(), (MAX_EQ_Q * MIN_EQ_Q).sqrt());
+
+        eq.set_q(1, 2.0).unwrap();
+        let value = eq.get_float(Parameter::EqQ(1)).unwrap();
+        assert!(value > 0.0 && value < 0.5);
+        assert_quantized(value, Q_STEPS);
+        assert!((eq.q(1).unwrap() - 2.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn maps_types_to_indexes() {
+        let mut client = emulator::start();
+        let mut eq = client.eq(Strip::Channel(4)).unwrap();
+        for (index, &eq_type) in EQ_TYPES.iter().enumerate() {
+            eq.set_eq_type(2, eq_type).unwrap();
+            assert_eq!(get(eq.client, eq.address(Parameter::EqType(2)).unwrap()).unwrap(),
+                       Argument::Integer(index as i32));
+            assert_eq!(eq.eq_type(2).unwrap(), eq_type);
+        }
+        eq.set(Parameter::EqType(2), 6.into()).unwrap();
+        assert_eq!(eq.eq_type(2).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert_eq!(EqType::from_index(-1), None);
+    }
+
+    #[test]
+    fn switches_the_eq() {
+        let mut client = emulator::start();
+        let mut eq = client.eq(Strip::Channel(5)).unwrap();
+        for &on in &[true, false] {
+            eq.set_on(on).unwrap();
+            assert_eq!(eq.on().unwrap(), on);
+        }
+    }
+
+    #[test]
+    fn has_six_bands_on_buses_and_the_main_mix() {
+        let mut client = emulator::start();
+        assert!(client.eq(Strip::Channel(1)).unwrap().set_gain(5, 0.0).is_err());
+        // The emulator is an XR12, with 2 buses.
+        for &strip in &[Strip::Bus(1), Strip::Bus(2), Strip::Main] {
+            let mut eq = client.eq(strip).unwrap();
+            assert_eq!(eq.bands(), 6);
+            let band = EqBand {
+                eq_type: EqType::HighShelf,
+                frequency: MAX_EQ_HZ,
+                gain: -6.0,
+                q: MIN_EQ_Q,
+            };
+            eq.set_band(6, &band).unwrap();
+            let curve = eq.curve().unwrap();
+            assert_eq!(curve.len(), 6);
+            assert_eq!(curve[5].eq_type, band.eq_type);
+            assert_close(curve[5].frequency, band.frequency);
+            assert_eq!(curve[5].gain, band.gain);
+            assert_close(curve[5].q, band.q);
+            for &band in &[0, 7] {
+                assert_eq!(eq.set_gain(band, 0.0).unwrap_err().kind(),
+                           io::ErrorKind::InvalidInput);
+                assert_eq!(eq.gain(band).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+            }
+        }
+        let error = client.eq(Strip::Dca(1)).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+}
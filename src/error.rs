@@ -0,0 +1,435 @@
+//! Errors produced while encoding, decoding and converting OSC packets.
+
+use core::fmt;
+#[cfg(any(feature = "std", all(feature = "serde", feature = "alloc")))]
+use alloc::string::String;
+#[cfg(all(feature = "serde", feature = "alloc"))]
+use alloc::string::ToString;
+#[cfg(all(feature = "serde", feature = "alloc"))]
+use serde::{de, ser};
+#[cfg(feature = "std")]
+use std::error::Error;
+#[cfg(feature = "config")]
+use std::io;
+#[cfg(feature = "config")]
+use std::path::PathBuf;
+
+/// An error encountered while decoding an OSC packet.
+///
+/// Each variant carries the byte offset, from the start of the packet, of the field that could
+/// not be decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The packet ended before a field of `len` bytes could be read.
+    Truncated { offset: usize, len: usize },
+    /// A string has no null terminator.
+    Unterminated { offset: usize },
+    /// A field is not padded out to a 4-byte boundary, or in strict mode, the padding contains
+    /// non-zero bytes.
+    BadPadding { offset: usize },
+    /// The type tag string does not start with a `,`.
+    MissingComma { offset: usize },
+    /// An argument has a type tag this decoder does not understand.
+    UnknownTag { offset: usize, tag: char },
+    /// A string is not valid UTF-8.
+    InvalidUtf8 { offset: usize },
+    /// A blob or bundle element declares a length of `len` bytes, more than the rest of the
+    /// packet holds.
+    LengthOverflow { offset: usize, len: u32 },
+    /// A bundle does not start with the `#bundle` string.
+    NotBundle { offset: usize },
+    /// A `c` argument is not a valid Unicode scalar value.
+    InvalidChar { offset: usize },
+    /// An array's `[` and `]` type tags do not match up.
+    UnbalancedArray { offset: usize },
+    /// In strict mode, bytes are left over after a command's last argument.
+    TrailingBytes { offset: usize },
+    /// A bundle or array is nested more than
+    /// [`MAX_BUNDLE_DEPTH`](constant.MAX_BUNDLE_DEPTH.html) or
+    /// [`MAX_ARRAY_DEPTH`](constant.MAX_ARRAY_DEPTH.html) deep.
+    TooDeep { offset: usize },
+}
+
+impl DecodeError {
+
+    /// Returns the byte offset of the field that could not be decoded.
+    pub fn offset(&self) -> usize {
+        match *self {
+            DecodeError::Truncated { offset, .. } |
+            DecodeError::Unterminated { offset } |
+            DecodeError::BadPadding { offset } |
+            DecodeError::MissingComma { offset } |
+            DecodeError::UnknownTag { offset, .. } |
+            DecodeError::InvalidUtf8 { offset } |
+            DecodeError::LengthOverflow { offset, .. } |
+            DecodeError::NotBundle { offset } |
+            DecodeError::InvalidChar { offset } |
+            DecodeError::UnbalancedArray { offset } |
+            DecodeError::TrailingBytes { offset } |
+            DecodeError::TooDeep { offset } => offset,
+        }
+    }
+
+    /// Shifts the offset of the error by `n` bytes, used to make an offset relative to a
+    /// sub-slice of the packet relative to the packet itself.
+    pub(crate) fn offset_by(mut self, n: usize) -> DecodeError {
+        match self {
+            DecodeError::Truncated { ref mut offset, .. } |
+            DecodeError::Unterminated { ref mut offset } |
+            DecodeError::BadPadding { ref mut offset } |
+            DecodeError::MissingComma { ref mut offset } |
+            DecodeError::UnknownTag { ref mut offset, .. } |
+            DecodeError::InvalidUtf8 { ref mut offset } |
+            DecodeError::LengthOverflow { ref mut offset, .. } |
+            DecodeError::NotBundle { ref mut offset } |
+            DecodeError::InvalidChar { ref mut offset } |
+            DecodeError::UnbalancedArray { ref mut offset } |
+            DecodeError::TrailingBytes { ref mut offset } |
+            DecodeError::TooDeep { ref mut offset } => *offset += n,
+        }
+        self
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DecodeError::Truncated { offset, len } => {
+                write!(f, "truncated packet: expected {} bytes at offset {}", len, offset)
+            },
+            DecodeError::Unterminated { offset } => {
+                write!(f, "string at offset {} has no null terminator", offset)
+            },
+            DecodeError::BadPadding { offset } => {
+                write!(f, "bad padding at offset {}", offset)
+            },
+            DecodeError::MissingComma { offset } => {
+                write!(f, "type tag string at offset {} does not start with ','", offset)
+            },
+            DecodeError::UnknownTag { offset, tag } => {
+                write!(f, "unknown type tag {:?} for argument at offset {}", tag, offset)
+            },
+            DecodeError::InvalidUtf8 { offset } => {
+                write!(f, "string at offset {} is not valid UTF-8", offset)
+            },
+            DecodeError::LengthOverflow { offset, len } => {
+                write!(f, "field at offset {} declares {} bytes, past the end of the packet",
+                       offset, len)
+            },
+            DecodeError::NotBundle { offset } => {
+                write!(f, "bundle at offset {} does not start with \"#bundle\"", offset)
+            },
+            DecodeError::InvalidChar { offset } => {
+                write!(f, "character at offset {} is not a valid Unicode scalar value", offset)
+            },
+            DecodeError::UnbalancedArray { offset } => {
+                write!(f, "unbalanced array type tags for argument at offset {}", offset)
+            },
+            DecodeError::TrailingBytes { offset } => {
+                write!(f, "unexpected bytes after the last argument at offset {}", offset)
+            },
+            DecodeError::TooDeep { offset } => {
+                write!(f, "bundle or array at offset {} is nested too deep", offset)
+            },
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for DecodeError {}
+
+/// An error encountered while encoding an OSC packet into a fixed-size buffer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncodeError {
+    /// The packet needs `needed` bytes, but the buffer only holds `available`.
+    BufferTooSmall { needed: usize, available: usize },
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EncodeError::BufferTooSmall { needed, available } => {
+                write!(f, "buffer too small: need {} bytes, have {}", needed, available)
+            },
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for EncodeError {}
+
+/// An error converting an argument to a Rust type that does not match its type tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TypeError {
+    /// The type tag the conversion expected.
+    pub expected: char,
+    /// The type tag of the argument.
+    pub found: char,
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "expected argument of type {:?}, found {:?}", self.expected, self.found)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for TypeError {}
+
+/// An error creating or parsing the address of a mixer parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressError {
+    /// The address is not one of the parameters this crate has a type for.
+    Unknown,
+    /// A strip's or meter bank's number is outside the range `min..=max`.
+    OutOfRange { kind: &'static str, number: u8, min: u8, max: u8 },
+    /// The strip does not have the parameter, such as a DCA group's pan.
+    NoSuchParameter,
+    /// The mixer is not an X Air mixer, so its addresses are laid out differently.
+    UnsupportedModel,
+}
+
+impl fmt::Display for AddressError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AddressError::Unknown => f.write_str("not a known mixer parameter address"),
+            AddressError::OutOfRange { kind, number, min, max } => {
+                write!(f, "there is no {} {}; they are numbered {} to {}", kind, number, min, max)
+            },
+            AddressError::NoSuchParameter => f.write_str("the strip does not have that parameter"),
+            AddressError::UnsupportedModel => {
+                f.write_str("the mixer's addresses are not laid out like an X Air mixer's")
+            },
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for AddressError {}
+
+/// An error encountered while parsing a command from its text form.
+///
+/// Each variant carries the byte offset, from the start of the text, of the part that could not
+/// be parsed.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// The text does not start with an address pattern beginning with `/`.
+    MissingAddress { offset: usize },
+    /// The type tag string does not start with a `,`.
+    MissingComma { offset: usize },
+    /// The type tag string contains a tag the parser does not understand.
+    UnknownTag { offset: usize, tag: char },
+    /// An array's `[` and `]` type tags do not match up.
+    UnbalancedArray { offset: usize },
+    /// The text ended before a value for every type tag was read.
+    MissingValue { offset: usize },
+    /// A value could not be parsed as its type tag requires.
+    InvalidValue { offset: usize, tag: char },
+    /// A quoted string or character has no closing quote.
+    UnterminatedQuote { offset: usize },
+    /// There is more text after the value for the last type tag.
+    TrailingText { offset: usize },
+    /// An array is nested more than [`MAX_ARRAY_DEPTH`](constant.MAX_ARRAY_DEPTH.html) deep.
+    TooDeep { offset: usize },
+}
+
+#[cfg(feature = "alloc")]
+impl ParseError {
+
+    /// Returns the byte offset of the text that could not be parsed.
+    pub fn offset(&self) -> usize {
+        match *self {
+            ParseError::MissingAddress { offset } |
+            ParseError::MissingComma { offset } |
+            ParseError::UnknownTag { offset, .. } |
+            ParseError::UnbalancedArray { offset } |
+            ParseError::MissingValue { offset } |
+            ParseError::InvalidValue { offset, .. } |
+            ParseError::UnterminatedQuote { offset } |
+            ParseError::TrailingText { offset } |
+            ParseError::TooDeep { offset } => offset,
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseError::MissingAddress { offset } => {
+                write!(f, "expected an address starting with '/' at offset {}", offset)
+            },
+            ParseError::MissingComma { offset } => {
+                write!(f, "type tag string at offset {} does not start with ','", offset)
+            },
+            ParseError::UnknownTag { offset, tag } => {
+                write!(f, "unknown type tag {:?} at offset {}", tag, offset)
+            },
+            ParseError::UnbalancedArray { offset } => {
+                write!(f, "unbalanced array type tags at offset {}", offset)
+            },
+            ParseError::MissingValue { offset } => {
+                write!(f, "expected another value at offset {}", offset)
+            },
+            ParseError::InvalidValue { offset, tag } => {
+                write!(f, "value at offset {} is not valid for type tag {:?}", offset, tag)
+            },
+            ParseError::UnterminatedQuote { offset } => {
+                write!(f, "quoted value at offset {} has no closing quote", offset)
+            },
+            ParseError::TrailingText { offset } => {
+                write!(f, "unexpected text after the last value at offset {}", offset)
+            },
+            ParseError::TooDeep { offset } => {
+                write!(f, "array at offset {} is nested too deep", offset)
+            },
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for ParseError {}
+
+/// An error mapping a Rust value to or from a list of OSC arguments with serde.
+#[cfg(all(feature = "serde", feature = "alloc"))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum SerdeError {
+    /// A custom error raised by a `Serialize` or `Deserialize` implementation.
+    Message(String),
+    /// The value has no OSC representation, such as a map.
+    Unsupported(&'static str),
+    /// There were fewer arguments than the value has fields.
+    MissingArgument,
+    /// There were arguments left over after the value was deserialized.
+    TrailingArguments { count: usize },
+}
+
+#[cfg(all(feature = "serde", feature = "alloc"))]
+impl fmt::Display for SerdeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SerdeError::Message(ref message) => f.write_str(message),
+            SerdeError::Unsupported(what) => {
+                write!(f, "{} cannot be mapped to OSC arguments", what)
+            },
+            SerdeError::MissingArgument => f.write_str("too few arguments"),
+            SerdeError::TrailingArguments { count } => {
+                write!(f, "{} arguments left over", count)
+            },
+        }
+    }
+}
+
+#[cfg(all(feature = "serde", feature = "alloc"))]
+impl ser::StdError for SerdeError {}
+
+#[cfg(all(feature = "serde", feature = "alloc"))]
+impl ser::Error for SerdeError {
+    fn custom<T: fmt::Display>(message: T) -> SerdeError {
+        SerdeError::Message(message.to_string())
+    }
+}
+
+#[cfg(all(feature = "serde", feature = "alloc"))]
+impl de::Error for SerdeError {
+    fn custom<T: fmt::Display>(message: T) -> SerdeError {
+        SerdeError::Message(message.to_string())
+    }
+
+    /// Serde reports a struct or tuple with fewer arguments than fields this way.
+    fn invalid_length(_len: usize, _expected: &dyn de::Expected) -> SerdeError {
+        SerdeError::MissingArgument
+    }
+}
+
+/// An error reading a mixer's reply to `/info` or `/xinfo`.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InfoError {
+    /// The command is not a reply to `/info` or `/xinfo`.
+    UnexpectedAddress { address: String },
+    /// The reply has no argument at `index`.
+    MissingArgument { index: usize },
+    /// The argument at `index` is not a string.
+    NotString { index: usize, found: char },
+    /// The IP address in an `/xinfo` reply could not be parsed.
+    InvalidAddress { address: String },
+    /// The console model is not one this crate knows about.
+    UnknownModel { model: String },
+    /// A version is not two or three numbers separated by dots, the second of two digits.
+    InvalidVersion { version: String },
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for InfoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InfoError::UnexpectedAddress { ref address } => {
+                write!(f, "{} is not a reply to /info or /xinfo", address)
+            },
+            InfoError::MissingArgument { index } => {
+                write!(f, "reply has no argument {}", index)
+            },
+            InfoError::NotString { index, found } => {
+                write!(f, "expected argument {} to be a string, found {:?}", index, found)
+            },
+            InfoError::InvalidAddress { ref address } => {
+                write!(f, "invalid IP address {:?}", address)
+            },
+            InfoError::UnknownModel { ref model } => write!(f, "unknown mixer model {:?}", model),
+            InfoError::InvalidVersion { ref version } => {
+                write!(f, "invalid version {:?}", version)
+            },
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for InfoError {}
+
+/// An error loading the mixer endpoint from flags, the environment or a config file.
+#[cfg(feature = "config")]
+#[derive(Debug)]
+pub enum ConfigError {
+    /// A flag that takes a value was the last argument.
+    MissingValue { flag: String },
+    /// A setting has a value that could not be parsed, such as a port that is not a number.
+    InvalidValue { name: String, value: String },
+    /// The config file has a key that is not a known setting.
+    UnknownKey { path: PathBuf, key: String },
+    /// The config file could not be read.
+    Io { path: PathBuf, error: io::Error },
+    /// The config file is not valid TOML.
+    Toml { path: PathBuf, error: ::toml::de::Error },
+}
+
+#[cfg(feature = "config")]
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConfigError::MissingValue { ref flag } => write!(f, "{} requires a value", flag),
+            ConfigError::InvalidValue { ref name, ref value } => {
+                write!(f, "invalid value {:?} for {}", value, name)
+            },
+            ConfigError::UnknownKey { ref path, ref key } => {
+                write!(f, "{}: unknown setting {:?}", path.display(), key)
+            },
+            ConfigError::Io { ref path, ref error } => write!(f, "{}: {}", path.display(), error),
+            ConfigError::Toml { ref path, ref error } => {
+                write!(f, "{}: {}", path.display(), error)
+            },
+        }
+    }
+}
+
+#[cfg(feature = "config")]
+impl Error for ConfigError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            ConfigError::Io { ref error, .. } => Some(error),
+            ConfigError::Toml { ref error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//! Converting fader positions to and from decibels.
+
+/// The number of positions a fader can take. Fader values are multiples of `1 / 1023`.
+pub const FADER_STEPS: u32 = 1024;
+
+/// The loudest a fader can be set to, in dB.
+pub const MAX_FADER_DB: f32 = 10.0;
+
+/// Rounds a fader value to the nearest position the mixer can take, clamping it to 0.0 to 1.0.
+pub fn quantize_fader(value: f32) -> f32 {
+    let max = (FADER_STEPS - 1) as f32;
+    if value.is_nan() || value <= 0.0 {
+        return 0.0;
+    }
+    if value >= 1.0 {
+        return 1.0;
+    }
+    // `value` is positive, so adding 0.5 and truncating rounds to the nearest step.
+    (value * max + 0.5) as u32 as f32 / max
+}
+
+/// Converts a fader value from 0.0 to 1.0 to its level in dB, as the mixer shows it.
+///
+/// The fader follows a law of four straight segments, with each quarter of the travel above
+/// 0.25 covering 10 dB:
+///
+/// | Fader value    | Level              |
+/// |----------------|--------------------|
+/// | 0.5 to 1.0     | -10 dB to +10 dB   |
+/// | 0.25 to 0.5    | -30 dB to -10 dB   |
+/// | 0.0625 to 0.25 | -60 dB to -30 dB   |
+/// | 0.0 to 0.0625  | -90 dB to -60 dB   |
+///
+/// A value of 0.0 is `-inf` dB. The value is first rounded to one of the fader's 1024
+/// positions, so the result is the level the mixer would actually be set to.
+///
+/// ```
+/// use april_2018_challenge::{db_to_fader, fader_to_db};
+///
+/// assert_eq!(fader_to_db(1.0), 10.0);
+/// assert_eq!(fader_to_db(0.0), std::f32::NEG_INFINITY);
+/// assert!((fader_to_db(0.75) - 0.0).abs() < 0.05);
+///
+/// // Every fader position survives a trip through dB.
+/// for step in 0..1024 {
+///     let value = step as f32 / 1023.0;
+///     assert_eq!(db_to_fader(fader_to_db(value)), value);
+/// }
+/// ```
+pub fn fader_to_db(value: f32) -> f32 {
+    let f = quantize_fader(value);
+    if f >= 0.5 {
+        40.0 * f - 30.0
+    } else if f >= 0.25 {
+        80.0 * f - 50.0
+    } else if f >= 0.0625 {
+        160.0 * f - 70.0
+    } else if f > 0.0 {
+        480.0 * f - 90.0
+    } else {
+        f32::NEG_INFINITY
+    }
+}
+
+/// Converts a level in dB to the fader value that comes nearest to it, using the law described
+/// under [`fader_to_db`](fn.fader_to_db.html).
+///
+/// Levels above +10 dB give 1.0, and levels at or below -90 dB, including `-inf`, give 0.0.
+pub fn db_to_fader(db: f32) -> f32 {
+    let f = if db.is_nan() || db <= -90.0 {
+        0.0
+    } else if db >= -10.0 {
+        (db + 30.0) / 40.0
+    } else if db >= -30.0 {
+        (db + 50.0) / 80.0
+    } else if db >= -60.0 {
+        (db + 70.0) / 160.0
+    } else {
+        (db + 90.0) / 480.0
+    };
+    quantize_fader(f)
+}
@@ -0,0 +1,316 @@
+//! What a mixer reports about itself in reply to `/info` and `/xinfo`.
+
+use core::fmt;
+use core::str::FromStr;
+use std::net::IpAddr;
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use argument::Argument;
+use command::Command;
+use error::InfoError;
+
+/// A model of mixer in the X32 and X Air families.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Model {
+    /// The X Air XR12.
+    Xr12,
+    /// The X Air XR16.
+    Xr16,
+    /// The X Air XR18.
+    Xr18,
+    /// The full-size X32 console.
+    X32,
+    /// The X32 Compact.
+    X32Compact,
+    /// The X32 Producer.
+    X32Producer,
+    /// The X32 Rack.
+    X32Rack,
+    /// The X32 Core.
+    X32Core,
+}
+
+impl Model {
+
+    /// Returns the name the mixer reports for this model, such as `XR12` or `X32RACK`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Model::Xr12 => "XR12",
+            Model::Xr16 => "XR16",
+            Model::Xr18 => "XR18",
+            Model::X32 => "X32",
+            Model::X32Compact => "X32C",
+            Model::X32Producer => "X32P",
+            Model::X32Rack => "X32RACK",
+            Model::X32Core => "X32CORE",
+        }
+    }
+
+    /// Returns whether this is an X Air mixer, which has a different address space from the
+    /// X32 family.
+    pub fn is_x_air(self) -> bool {
+        matches!(self, Model::Xr12 | Model::Xr16 | Model::Xr18)
+    }
+}
+
+impl fmt::Display for Model {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Model {
+    type Err = InfoError;
+
+    /// Reads a model from the name the mixer reports, ignoring case.
+    fn from_str(s: &str) -> Result<Model, InfoError> {
+        let models = [
+            Model::Xr12,
+            Model::Xr16,
+            Model::Xr18,
+            Model::X32,
+            Model::X32Compact,
+            Model::X32Producer,
+            Model::X32Rack,
+            Model::X32Core,
+        ];
+        models.iter()
+            .cloned()
+            .find(|model| model.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| InfoError::UnknownModel { model: s.to_string() })
+    }
+}
+
+/// A firmware or server version, such as `1.17` or `V0.04`.
+///
+/// The mixers always write the second number with two digits, so `1.5` is not a version but
+/// `1.05` is. Versions compare by their numbers, so `1.17` is later than `1.05`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    /// The third number of the version, or 0 if it has only two.
+    pub patch: u32,
+}
+
+impl Version {
+
+    /// Creates a version from its numbers.
+    pub fn new(major: u32, minor: u32, patch: u32) -> Version {
+        Version { major, minor, patch }
+    }
+}
+
+impl fmt::Display for Version {
+    /// Writes the version as the mixers do, with at least two digits after the first dot, and
+    /// the third number only if it is not 0.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{:02}", self.major, self.minor)?;
+        if self.patch != 0 {
+            write!(f, ".{}", self.patch)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Version {
+    type Err = InfoError;
+
+    /// Reads a version of two or three numbers separated by dots, optionally preceded by a `V`.
+    /// The second number must have two digits, so that the version prints the same way.
+    fn from_str(s: &str) -> Result<Version, InfoError> {
+        let invalid = || InfoError::InvalidVersion { version: s.to_string() };
+        let digits = s.trim_start_matches(['V', 'v']);
+        let numbers: Vec<&str> = digits.split('.').collect();
+        let (major, minor, patch) = match numbers[..] {
+            [major, minor] => (major, minor, "0"),
+            [major, minor, patch] => (major, minor, patch),
+            _ => return Err(invalid()),
+        };
+        if minor.len() != 2 {
+            return Err(invalid());
+        }
+        let number = |n: &str| {
+            if n.is_empty() || !n.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(invalid());
+            }
+            n.parse().map_err(|_| invalid())
+        };
+        Ok(Version { major: number(major)?, minor: number(minor)?, patch: number(patch)? })
+    }
+}
+
+/// What a mixer reports about itself in reply to `/info` or `/xinfo`.
+///
+/// ```
+/// use std::net::{IpAddr, Ipv4Addr};
+///
+/// use april_2018_challenge::{Command, MixerInfo, Model, Version};
+///
+/// let reply = Command::new("/info").arg("V0.04").arg("XR12-0A-1B-2C").arg("XR12").arg("1.17");
+/// let info = MixerInfo::from_command(&reply, IpAddr::V4(Ipv4Addr::LOCALHOST)).unwrap();
+/// assert_eq!(info.model, Model::Xr12);
+/// assert!(info.firmware >= Version::new(1, 12, 0));
+/// assert_eq!(info.server_version, Some(Version::new(0, 4, 0)));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MixerInfo {
+    /// The address the reply came from.
+    pub ip: IpAddr,
+    /// The version of the mixer's OSC server, which only `/info` reports.
+    pub server_version: Option<Version>,
+    /// The name the mixer has been given, for example `XR12-0A-1B-2C`.
+    pub name: String,
+    /// The mixer's model.
+    pub model: Model,
+    /// The mixer's firmware version.
+    pub firmware: Version,
+}
+
+impl MixerInfo {
+
+    /// Reads the reply to an `/info` or `/xinfo` command, received from `ip`.
+    ///
+    /// Both replies carry four strings: the OSC server version for `/info`, or the mixer's IP
+    /// address for `/xinfo`, then the name, model and firmware version. Any further arguments
+    /// are ignored.
+    pub fn from_command(command: &Command, ip: IpAddr) -> Result<MixerInfo, InfoError> {
+        let is_info = match command.address_pattern.as_str() {
+            "/info" => true,
+            "/xinfo" => false,
+            address => {
+                return Err(InfoError::UnexpectedAddress { address: address.to_string() });
+            },
+        };
+        let string = |index: usize| match command.arguments.get(index) {
+            Some(&Argument::String(ref s)) | Some(&Argument::Symbol(ref s)) => Ok(s.as_str()),
+            Some(argument) => Err(InfoError::NotString { index, found: argument.tag() as char }),
+            None => Err(InfoError::MissingArgument { index }),
+        };
+
+        let first = string(0)?;
+        let server_version = if is_info {
+            Some(first.parse()?)
+        } else {
+            first.parse::<IpAddr>()
+                .map_err(|_| InfoError::InvalidAddress { address: first.to_string() })?;
+            None
+        };
+        Ok(MixerInfo {
+            ip,
+            server_version,
+            name: string(1)?.to_string(),
+            model: string(2)?.parse()?,
+            firmware: string(3)?.parse()?,
+        })
+    }
+}
+
+impl fmt::Display for MixerInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} at {}, firmware {}", self.model, self.name, self.ip, self.firmware)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prints_versions_as_they_were_read() {
+        for &s in &["1.17", "0.04", "1.05", "10.00", "1.17.3", "4.06.12"] {
+            assert_eq!(s.parse::<Version>().unwrap().to_string(), s);
+        }
+        assert_eq!("V0.04".parse(), Ok(Version::new(0, 4, 0)));
+        assert_eq!("v1.17.3".parse(), Ok(Version::new(1, 17, 3)));
+        assert!(Version::new(1, 17, 0) > Version::new(1, 5, 0));
+    }
+
+    #[test]
+    fn rejects_malformed_versions() {
+        for &s in &["", "1", "1.5", "1.123", "1.05.", ".05", "1.05.1.1", "1.0a", "1..05",
+                    "1.-5", "V", "1.05 ", "99999999999.05"] {
+            assert_eq!(s.parse::<Version>(),
+                       Err(InfoError::InvalidVersion { version: s.to_string() }));
+        }
+    }
+
+    fn read(address: &str, arguments: &[Argument]) -> Result<MixerInfo, InfoError> {
+        let command = Command {
+            address_pattern: address.to_string(),
+            arguments: arguments.to_vec(),
+        };
+        MixerInfo::from_command(&command, IpAddr::from([192, 168, 1, 10]))
+    }
+
+    fn strings(strings: &[&str]) -> Vec<Argument> {
+        strings.iter().map(|&s| Argument::from(s)).collect()
+    }
+
+    #[test]
+    fn reads_info_and_xinfo_replies() {
+        let info = read("/info", &strings(&["V0.04", "XR12-0A-1B-2C", "XR12", "1.17"])).unwrap();
+        assert_eq!(info, MixerInfo {
+            ip: IpAddr::from([192, 168, 1, 10]),
+            server_version: Some(Version::new(0, 4, 0)),
+            name: "XR12-0A-1B-2C".to_string(),
+            model: Model::Xr12,
+            firmware: Version::new(1, 17, 0),
+        });
+        assert_eq!(info.to_string(), "XR12 XR12-0A-1B-2C at 192.168.1.10, firmware 1.17");
+
+        let mut arguments = strings(&["192.168.1.10", "Stage", "xr18", "1.18"]);
+        arguments[1] = Argument::Symbol("Stage".to_string());
+        arguments.push(Argument::Integer(1));
+        let xinfo = read("/xinfo", &arguments).unwrap();
+        assert_eq!(xinfo.server_version, None);
+        assert_eq!(xinfo.name, "Stage");
+        assert_eq!(xinfo.model, Model::Xr18);
+    }
+
+    #[test]
+    fn rejects_replies_to_other_commands() {
+        assert_eq!(read("/status", &strings(&["active", "192.168.1.10", "XR12"])),
+                   Err(InfoError::UnexpectedAddress { address: "/status".to_string() }));
+    }
+
+    #[test]
+    fn rejects_missing_and_non_string_arguments() {
+        assert_eq!(read("/info", &strings(&["V0.04", "XR12-0A-1B-2C", "XR12"])),
+                   Err(InfoError::MissingArgument { index: 3 }));
+        assert_eq!(read("/xinfo", &[]), Err(InfoError::MissingArgument { index: 0 }));
+
+        let mut arguments = strings(&["V0.04", "XR12-0A-1B-2C", "XR12", "1.17"]);
+        arguments[2] = Argument::Integer(12);
+        assert_eq!(read("/info", &arguments), Err(InfoError::NotString { index: 2, found: 'i' }));
+        arguments[0] = Argument::Float(0.04);
+        assert_eq!(read("/info", &arguments), Err(InfoError::NotString { index: 0, found: 'f' }));
+    }
+
+    #[test]
+    fn rejects_a_bad_ip_address() {
+        assert_eq!(read("/xinfo", &strings(&["192.168.1", "Stage", "XR18", "1.18"])),
+                   Err(InfoError::InvalidAddress { address: "192.168.1".to_string() }));
+        // `/info` has a version where `/xinfo` has the address.
+        assert_eq!(read("/xinfo", &strings(&["V0.04", "Stage", "XR18", "1.18"])),
+                   Err(InfoError::InvalidAddress { address: "V0.04".to_string() }));
+    }
+
+    #[test]
+    fn rejects_an_unknown_model() {
+        assert_eq!(read("/info", &strings(&["V0.04", "Desk", "M32", "1.17"])),
+                   Err(InfoError::UnknownModel { model: "M32".to_string() }));
+        assert_eq!("x32rack".parse(), Ok(Model::X32Rack));
+        assert_eq!("".parse::<Model>(), Err(InfoError::UnknownModel { model: String::new() }));
+    }
+
+    #[test]
+    fn rejects_malformed_versions_in_replies() {
+        assert_eq!(read("/info", &strings(&["V0.4", "XR12-0A-1B-2C", "XR12", "1.17"])),
+                   Err(InfoError::InvalidVersion { version: "V0.4".to_string() }));
+        assert_eq!(read("/xinfo", &strings(&["192.168.1.10", "Stage", "XR18", "one"])),
+                   Err(InfoError::InvalidVersion { version: "one".to_string() }));
+    }
+}
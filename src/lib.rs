@@ -0,0 +1,217 @@
+//! A codec for Open Sound Control (OSC) packets, as spoken by the Behringer X-series mixers.
+//!
+//! Build a [`Command`](struct.Command.html), encode it to a buffer and send it over any
+//! transport; decode received datagrams back into a `Command`, a
+//! [`Bundle`](struct.Bundle.html), or either one as a [`Packet`](enum.Packet.html).
+//!
+//! ```
+//! use april_2018_challenge::Command;
+//!
+//! let command = Command::new("/ch/01/mix/fader").arg(0.75);
+//! let mut buf = Vec::new();
+//! command.encode(&mut buf);
+//!
+//! assert_eq!(Command::decode(&buf).unwrap(), command);
+//! ```
+//!
+//! Commands also have a text form, printed by `Display` and parsed by `FromStr`, such as
+//! `/ch/01/mix/fader ,f 0.75`.
+//!
+//! The X Air mixers' parameters can be named with a typed [`Address`](struct.Address.html),
+//! such as a channel's fader, rather than a hand-written string, and fader positions converted
+//! to and from decibels with [`fader_to_db`](fn.fader_to_db.html) and
+//! [`db_to_fader`](fn.db_to_fader.html).
+//!
+//! # Cargo features
+//!
+//! * `std` (default): `std::error::Error` impls, encoding to `std::io::Write`, conversions
+//!   between `Timetag` and `SystemTime`, framing packets over TCP streams with `FramedStream`,
+//!   finding mixers on the network with [`discover`](fn.discover.html), querying them and
+//!   following their changes with [`MixerClient`](struct.MixerClient.html), changing a
+//!   channel's settings with [`ChannelStrip`](struct.ChannelStrip.html) and a strip's EQ with
+//!   [`Equalizer`](struct.Equalizer.html), and the
+//!   [`Emulator`](struct.Emulator.html) that stands in for a mixer in tests. Implies `alloc`.
+//! * `config` (default): loading the mixer's address from flags, environment variables or a
+//!   TOML file with [`Endpoint`](struct.Endpoint.html). Implies `std`.
+//! * `alloc`: the owned `Command`, `Argument`, `Bundle` and `Packet` types, the `Dispatcher`,
+//!   and the text form of commands.
+//! * `serde`: mapping Rust types to and from argument lists with
+//!   [`to_arguments`](fn.to_arguments.html) and [`from_arguments`](fn.from_arguments.html).
+//!   Requires `alloc`.
+//!
+//! With neither feature the crate is `no_std`, and packets can be decoded with
+//! [`CommandRef`](struct.CommandRef.html).
+
+#![no_std]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+extern crate byteorder;
+#[cfg(feature = "config")]
+extern crate toml;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+#[macro_use]
+extern crate serde_derive;
+
+#[macro_use]
+mod macros;
+
+mod address;
+mod argument;
+#[cfg(feature = "alloc")]
+mod bundle;
+#[cfg(feature = "std")]
+mod channel;
+#[cfg(feature = "std")]
+mod client;
+mod command;
+#[cfg(feature = "config")]
+mod config;
+#[cfg(feature = "alloc")]
+mod convert;
+#[cfg(all(feature = "serde", feature = "alloc"))]
+mod de;
+#[cfg(feature = "std")]
+mod discover;
+#[cfg(feature = "alloc")]
+mod dispatch;
+mod dump;
+#[cfg(feature = "std")]
+mod emulator;
+#[cfg(feature = "std")]
+mod eq;
+#[cfg(feature = "alloc")]
+mod encode;
+mod error;
+mod fader;
+#[cfg(feature = "std")]
+mod info;
+mod pattern;
+#[cfg(all(feature = "serde", feature = "alloc"))]
+mod ser;
+#[cfg(feature = "std")]
+mod setting;
+#[cfg(feature = "std")]
+mod stream;
+#[cfg(feature = "std")]
+mod subscription;
+#[cfg(feature = "alloc")]
+mod text;
+mod timetag;
+
+pub use address::{Address, Parameter, Strip, DCAS, FX_RETURNS, MAX_BUSES, MAX_CHANNELS};
+#[cfg(feature = "alloc")]
+pub use argument::Argument;
+pub use argument::{ArgumentRef, ArrayRef, Midi, Rgba};
+#[cfg(feature = "alloc")]
+pub use bundle::{Bundle, Commands, Packet};
+#[cfg(feature = "std")]
+pub use channel::{ChannelStrip, StripColor, MAX_GAIN_DB, MAX_LOW_CUT_HZ, MIN_GAIN_DB,
+                  MIN_LOW_CUT_HZ};
+#[cfg(feature = "std")]
+pub use client::{MixerClient, DEFAULT_RETRIES, DEFAULT_TIMEOUT};
+#[cfg(feature = "alloc")]
+pub use command::Command;
+pub use command::{Arguments, CommandRef};
+#[cfg(feature = "config")]
+pub use config::{Endpoint, DEFAULT_CONFIG_FILE, DEFAULT_HOST, DEFAULT_PORT};
+#[cfg(all(feature = "serde", feature = "alloc"))]
+pub use de::{from_arguments, from_command};
+#[cfg(feature = "std")]
+pub use discover::{discover, discover_at};
+#[cfg(feature = "alloc")]
+pub use dispatch::Dispatcher;
+pub use dump::{hex_dump, HexDump};
+#[cfg(feature = "std")]
+pub use emulator::{Emulator, EMULATOR_NAME};
+#[cfg(feature = "std")]
+pub use eq::{EqBand, EqType, Equalizer, MAX_EQ_GAIN_DB, MAX_EQ_HZ, MAX_EQ_Q, MIN_EQ_HZ, MIN_EQ_Q};
+pub use error::{AddressError, DecodeError, EncodeError, TypeError};
+#[cfg(feature = "config")]
+pub use error::ConfigError;
+#[cfg(feature = "std")]
+pub use error::InfoError;
+#[cfg(feature = "alloc")]
+pub use error::ParseError;
+#[cfg(all(feature = "serde", feature = "alloc"))]
+pub use error::SerdeError;
+pub use fader::{db_to_fader, fader_to_db, quantize_fader, FADER_STEPS, MAX_FADER_DB};
+#[cfg(feature = "std")]
+pub use info::{MixerInfo, Model, Version};
+pub use pattern::address_matches;
+#[cfg(all(feature = "serde", feature = "alloc"))]
+pub use ser::{to_arguments, to_command};
+#[cfg(feature = "std")]
+pub use stream::{FramedStream, Framing, MAX_FRAME_SIZE};
+#[cfg(feature = "std")]
+pub use subscription::{KeepAlive, MeterBank, Meters, Remote, METERS_INTERVAL, REMOTE_INTERVAL};
+pub use timetag::Timetag;
+
+use core::str;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+/// The deepest that bundles can be nested in a decoded packet, counting the outermost bundle
+/// as 1. Deeper packets fail to decode with `DecodeError::TooDeep`, rather than exhausting the
+/// stack.
+pub const MAX_BUNDLE_DEPTH: usize = 32;
+
+/// The deepest that array arguments can be nested, counting the outermost array as 1. Deeper
+/// arrays fail to decode with `DecodeError::TooDeep`, though they can still be encoded.
+pub const MAX_ARRAY_DEPTH: usize = 32;
+
+/// Pads the provided buffer with null bytes to be 4-byte aligned.
+#[cfg(feature = "alloc")]
+pub fn pad(buf: &mut Vec<u8>) {
+    let zeros: &[u8] = &[0; 3];
+    let m = buf.len() % 4;
+    if m != 0 {
+        buf.extend(&zeros[..4 - m]);
+    }
+}
+
+/// Encodes the provided string to the buffer.
+#[cfg(feature = "alloc")]
+pub fn encode_string(s: &str, buf: &mut Vec<u8>) {
+    buf.extend(s.as_bytes());
+    buf.push(0);
+    pad(buf);
+}
+
+/// Decodes a string from the buffer, returning the string value and the remaining buffer.
+///
+/// In strict mode the padding after the string must be all zero bytes.
+pub fn decode_string(buf: &[u8], strict: bool) -> Result<(&str, &[u8]), DecodeError> {
+    let idx = buf.iter()
+        .position(|&x| x == 0)
+        .ok_or(DecodeError::Unterminated { offset: 0 })?;
+    let s = str::from_utf8(&buf[..idx]).map_err(|_| DecodeError::InvalidUtf8 { offset: 0 })?;
+    let buf = unpad(buf, idx + 1, strict)?;
+    Ok((s, buf))
+}
+
+/// Skips a field of `len` bytes and the padding after it, returning the remaining buffer.
+///
+/// In strict mode the padding bytes must all be zero.
+pub(crate) fn unpad(buf: &[u8], len: usize, strict: bool) -> Result<&[u8], DecodeError> {
+    let end = (len + 3) & !3;
+    if end > buf.len() || (strict && buf[len..end].iter().any(|&x| x != 0)) {
+        return Err(DecodeError::BadPadding { offset: len });
+    }
+    Ok(&buf[end..])
+}
+
+/// Splits a fixed-size field of `len` bytes off the front of the buffer.
+pub(crate) fn split(buf: &[u8], len: usize) -> Result<(&[u8], &[u8]), DecodeError> {
+    if buf.len() < len {
+        return Err(DecodeError::Truncated { offset: 0, len });
+    }
+    Ok(buf.split_at(len))
+}
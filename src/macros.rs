@@ -0,0 +1,41 @@
+//! Macros for building commands.
+
+/// Builds a `Command` from an address pattern and a list of arguments.
+///
+/// Each argument is converted with `Argument::from`, so its type tag follows from its Rust type:
+/// integer literals become `i`, float literals `f`, and strings `s`.
+///
+/// ```
+/// #[macro_use]
+/// extern crate april_2018_challenge;
+///
+/// use april_2018_challenge::{Argument, Command};
+///
+/// # fn main() {
+/// let command = osc!("/ch/01/mix/fader", 0.75);
+/// assert_eq!(command, Command {
+///     address_pattern: "/ch/01/mix/fader".to_string(),
+///     arguments: vec![Argument::Float(0.75)],
+/// });
+/// # }
+/// ```
+///
+/// A single trailing comma is allowed, but not more:
+///
+/// ```compile_fail
+/// #[macro_use]
+/// extern crate april_2018_challenge;
+///
+/// # fn main() {
+/// let command = osc!("/play/note", 1,,);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! osc {
+    ($address:expr $(,)?) => {
+        $crate::Command::new($address)
+    };
+    ($address:expr, $($argument:expr),+ $(,)?) => {
+        $crate::Command::new($address)$(.arg($argument))+
+    };
+}
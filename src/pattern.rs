@@ -0,0 +1,301 @@
+//! OSC 1.0 address pattern matching.
+
+/// Returns whether an OSC address pattern matches an address.
+///
+/// Patterns support the OSC 1.0 wildcards, none of which match across a `/`:
+///
+/// * `?` matches any single character.
+/// * `*` matches any run of zero or more characters.
+/// * `[abc]` and `[a-z]` match any character in the set, and `[!abc]` any character not in it.
+///   A range may be written either way round, so `[z-a]` is the same as `[a-z]`.
+/// * `{foo,bar}` matches any one of the comma-separated strings. `{}` matches the empty string.
+///
+/// A pattern with an unclosed `[` or `{`, or with a `/` inside one, matches nothing.
+///
+/// Each `*` adds time in proportion to the length of the address, so a pattern with many of
+/// them cannot make matching take exponential time.
+///
+/// ```
+/// use april_2018_challenge::address_matches;
+///
+/// assert!(address_matches("/ch/*/mix/fader", "/ch/01/mix/fader"));
+/// assert!(address_matches("/ch/0[1-4]/mix/{on,fader}", "/ch/03/mix/on"));
+/// assert!(!address_matches("/ch/*", "/ch/01/mix/fader"));
+/// ```
+pub fn address_matches(pattern: &str, address: &str) -> bool {
+    // No wildcard crosses a `/`, so each part of the pattern between them can be matched against
+    // the same part of the address on its own.
+    let mut patterns = pattern.split('/');
+    let mut addresses = address.split('/');
+    loop {
+        match (patterns.next(), addresses.next()) {
+            (Some(pattern), Some(address)) => {
+                if !segment_matches(pattern.as_bytes(), address.as_bytes()) {
+                    return false;
+                }
+            },
+            (None, None) => return true,
+            _ => return false,
+        }
+    }
+}
+
+/// One element of a pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token<'a> {
+    /// A character that matches only itself.
+    Byte(u8),
+    /// `?`
+    Any,
+    /// `*`
+    Star,
+    /// The inside of a `[...]` expression.
+    Class(&'a [u8]),
+    /// The inside of a `{...}` expression.
+    Choices(&'a [u8]),
+    /// A `[` or `{` that is not closed.
+    Unclosed,
+}
+
+/// Splits the first token off a pattern, or returns `None` if the pattern is empty.
+fn token(pattern: &[u8]) -> Option<(Token<'_>, &[u8])> {
+    let (&c, rest) = pattern.split_first()?;
+    let close = match c {
+        b'?' => return Some((Token::Any, rest)),
+        b'*' => return Some((Token::Star, rest)),
+        b'[' => b']',
+        b'{' => b'}',
+        c => return Some((Token::Byte(c), rest)),
+    };
+    Some(match rest.iter().position(|&x| x == close) {
+        Some(end) if c == b'[' => (Token::Class(&rest[..end]), &rest[end + 1..]),
+        Some(end) => (Token::Choices(&rest[..end]), &rest[end + 1..]),
+        None => (Token::Unclosed, &[]),
+    })
+}
+
+/// Splits the tokens before the first `*` off a pattern, returning them and the rest of the
+/// pattern after the `*`, if there is one. Returns `None` if a `[` or `{` is not closed.
+fn split_chunk(pattern: &[u8]) -> Option<(&[u8], Option<&[u8]>)> {
+    let mut rest = pattern;
+    while let Some((token, after)) = token(rest) {
+        match token {
+            Token::Star => return Some((&pattern[..pattern.len() - rest.len()], Some(after))),
+            Token::Unclosed => return None,
+            _ => rest = after,
+        }
+    }
+    Some((pattern, None))
+}
+
+/// Returns whether a pattern matches an address, neither of which contain a `/`.
+fn segment_matches(pattern: &[u8], address: &[u8]) -> bool {
+    // The pattern is a list of chunks with no `*` in them, separated by `*`s. The first chunk
+    // must match at the start of the address and the last at its end. Each chunk in between
+    // can take the match that ends soonest, since that leaves the most for the chunks after it.
+    let (first, mut rest) = match split_chunk(pattern) {
+        Some((first, Some(rest))) => (first, rest),
+        Some((first, None)) => return chunk_match(first, address, true).is_some(),
+        None => return false,
+    };
+    let mut pos = match chunk_match(first, address, false) {
+        Some(end) => end,
+        None => return false,
+    };
+    loop {
+        match split_chunk(rest) {
+            Some((last, None)) => {
+                return (pos..=address.len())
+                    .any(|start| chunk_match(last, &address[start..], true).is_some());
+            },
+            Some((chunk, Some(after))) => {
+                pos = match earliest_end(chunk, address, pos) {
+                    Some(end) => end,
+                    None => return false,
+                };
+                rest = after;
+            },
+            None => return false,
+        }
+    }
+}
+
+/// Returns the soonest that a chunk with no `*` in it can end when matched anywhere in the
+/// address from `pos` on.
+fn earliest_end(chunk: &[u8], address: &[u8], pos: usize) -> Option<usize> {
+    let mut best: Option<usize> = None;
+    for start in pos..=address.len() {
+        if let Some(best) = best {
+            if start >= best {
+                break;
+            }
+        }
+        if let Some(len) = chunk_match(chunk, &address[start..], false) {
+            best = Some(best.map_or(start + len, |best| best.min(start + len)));
+        }
+    }
+    best
+}
+
+/// Returns the length of the shortest start of the address that a chunk with no `*` in it
+/// matches, or if `whole` is set, whether it matches the whole address.
+fn chunk_match(chunk: &[u8], address: &[u8], whole: bool) -> Option<usize> {
+    let mut chunk = chunk;
+    let mut i = 0;
+    while let Some((token, rest)) = token(chunk) {
+        match token {
+            Token::Byte(c) if address.get(i) == Some(&c) => {},
+            Token::Any if i < address.len() => {},
+            Token::Class(class) => match address.get(i) {
+                Some(&c) if class_matches(class, c) => {},
+                _ => return None,
+            },
+            Token::Choices(choices) => {
+                // Choices of different lengths leave different amounts for the rest of the
+                // chunk, so each is tried in turn.
+                let address = &address[i..];
+                return choices.split(|&c| c == b',')
+                    .filter(|choice| address.starts_with(choice))
+                    .filter_map(|choice| {
+                        chunk_match(rest, &address[choice.len()..], whole)
+                            .map(|len| i + choice.len() + len)
+                    })
+                    .min();
+            },
+            _ => return None,
+        }
+        i += 1;
+        chunk = rest;
+    }
+    if whole && i != address.len() {
+        None
+    } else {
+        Some(i)
+    }
+}
+
+/// Returns whether a character is in the set described by the inside of a `[...]` expression.
+fn class_matches(class: &[u8], c: u8) -> bool {
+    let (negate, class) = match class.split_first() {
+        Some((b'!', rest)) => (true, rest),
+        _ => (false, class),
+    };
+
+    let mut found = false;
+    let mut i = 0;
+    while i < class.len() {
+        // A '-' between two characters is a range; anywhere else it is literal.
+        if i + 2 < class.len() && class[i + 1] == b'-' {
+            let (low, high) = (class[i].min(class[i + 2]), class[i].max(class[i + 2]));
+            found |= low <= c && c <= high;
+            i += 3;
+        } else {
+            found |= class[i] == c;
+            i += 1;
+        }
+    }
+    found != negate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_any_single_character() {
+        assert!(address_matches("/ch/0?", "/ch/01"));
+        assert!(address_matches("/??", "/ab"));
+        assert!(!address_matches("/??", "/a"));
+        assert!(!address_matches("/??", "/abc"));
+        assert!(!address_matches("/a?b", "/a/b"));
+    }
+
+    #[test]
+    fn matches_runs_of_characters() {
+        assert!(address_matches("/*", "/"));
+        assert!(address_matches("/a*", "/a"));
+        assert!(address_matches("/*fader", "/mixfader"));
+        assert!(address_matches("/a*b*c", "/aXbYbZc"));
+        assert!(address_matches("/**", "/abc"));
+        assert!(address_matches("/*/*", "/a/b"));
+        assert!(!address_matches("/*", "/a/b"));
+        assert!(!address_matches("/a*c", "/abd"));
+        assert!(!address_matches("/*b*b", "/abab/"));
+    }
+
+    #[test]
+    fn matches_character_classes() {
+        assert!(address_matches("/[abc]", "/b"));
+        assert!(!address_matches("/[abc]", "/d"));
+        assert!(address_matches("/[!abc]", "/d"));
+        assert!(!address_matches("/[!abc]", "/a"));
+        assert!(address_matches("/[a-c]x", "/bx"));
+        assert!(!address_matches("/[a-c]x", "/dx"));
+        assert!(address_matches("/[!a-c]", "/d"));
+        assert!(address_matches("/[0-9a-f]", "/e"));
+        assert!(address_matches("/[*]", "/*"));
+        assert!(!address_matches("/[*]", "/a"));
+
+        // A '-' at either end is literal.
+        assert!(address_matches("/[-a]", "/-"));
+        assert!(address_matches("/[a-]", "/-"));
+        assert!(!address_matches("/[a-]", "/b"));
+    }
+
+    #[test]
+    fn matches_reversed_ranges() {
+        for c in "abc".chars() {
+            let address = ["/", c.encode_utf8(&mut [0; 4])].concat();
+            assert!(address_matches("/[c-a]", &address));
+        }
+        assert!(!address_matches("/[c-a]", "/d"));
+        assert!(address_matches("/[!c-a]", "/d"));
+    }
+
+    #[test]
+    fn matches_choices() {
+        assert!(address_matches("/mix/{on,fader}", "/mix/on"));
+        assert!(address_matches("/mix/{on,fader}", "/mix/fader"));
+        assert!(!address_matches("/mix/{on,fader}", "/mix/pan"));
+        assert!(address_matches("/{a,ab}c", "/abc"));
+        assert!(address_matches("/*{b,bc}d", "/abcd"));
+        assert!(address_matches("/a{}b", "/ab"));
+        assert!(address_matches("/a{,x}b", "/ab"));
+        assert!(address_matches("/a{,x}b", "/axb"));
+        assert!(!address_matches("/a{}b", "/axb"));
+        assert!(address_matches("/{*,?}", "/*"));
+        assert!(!address_matches("/{*,?}", "/a"));
+    }
+
+    #[test]
+    fn matches_nothing_with_an_unclosed_expression() {
+        assert!(!address_matches("/[abc", "/a"));
+        assert!(!address_matches("/[abc", "/[abc"));
+        assert!(!address_matches("/{a,b", "/a"));
+        assert!(!address_matches("/*{a", "/a"));
+        assert!(!address_matches("/a/[b", "/x/b"));
+    }
+
+    #[test]
+    fn never_matches_across_a_slash() {
+        assert!(!address_matches("/a/{b/c,d}", "/a/b/c"));
+        assert!(!address_matches("/a/{b/c,d}", "/a/d"));
+        assert!(!address_matches("/a[/]b", "/a/b"));
+        assert!(!address_matches("/a*", "/a/b"));
+        assert!(!address_matches("/*", "/a/"));
+        assert!(!address_matches("/a?b", "/a/b"));
+        assert!(!address_matches("/a[!x]b", "/a/b"));
+        assert!(address_matches("/*/b", "/a/b"));
+        assert!(!address_matches("/a/b", "/a/b/"));
+    }
+
+    #[test]
+    fn matches_many_stars_quickly() {
+        // Trying every split of the address between the stars would take far too long here.
+        let address = ["/", &"a".repeat(40)].concat();
+        assert!(!address_matches("/*a*a*a*a*a*a*b", &address));
+        assert!(address_matches("/*a*a*a*a*a*a*", &address));
+        let pattern = ["/", &"*a".repeat(200), "*b"].concat();
+        assert!(!address_matches(&pattern, &["/", &"a".repeat(2000)].concat()));
+    }
+}
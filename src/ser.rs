@@ -0,0 +1,378 @@
+//! Serializing Rust values into OSC argument lists with serde.
+
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use serde::ser::{self, Impossible, Serialize};
+
+use argument::Argument;
+use command::Command;
+use error::SerdeError;
+
+/// Serializes a value into a list of arguments.
+///
+/// The fields of a struct, or the elements of a tuple or sequence, become the arguments in
+/// order. Any other value becomes a single argument.
+///
+/// | Rust type                         | Type tag |
+/// |-----------------------------------|----------|
+/// | `bool`                            | `T`, `F` |
+/// | `i8`, `i16`, `i32`, `u8`, `u16`   | `i`      |
+/// | `i64`, `u32`, `u64`               | `h`      |
+/// | `f32`                             | `f`      |
+/// | `f64`                             | `d`      |
+/// | `char`                            | `c`      |
+/// | `&str`, `String`, unit variants   | `s`      |
+/// | byte slices (`serde_bytes`)       | `b`      |
+/// | `()`, `None`                      | `N`      |
+/// | nested structs, tuples, sequences | `[...]`  |
+///
+/// Maps and enum variants carrying data cannot be serialized.
+///
+/// ```
+/// extern crate april_2018_challenge;
+/// #[macro_use]
+/// extern crate serde_derive;
+///
+/// use april_2018_challenge::{to_arguments, Argument};
+///
+/// #[derive(Serialize)]
+/// struct FaderSet {
+///     level: f32,
+/// }
+///
+/// # fn main() {
+/// let arguments = to_arguments(&FaderSet { level: 0.75 }).unwrap();
+/// assert_eq!(arguments, [Argument::Float(0.75)]);
+/// # }
+/// ```
+pub fn to_arguments<T: ?Sized + Serialize>(value: &T) -> Result<Vec<Argument>, SerdeError> {
+    match value.serialize(ArgumentSerializer)? {
+        Argument::Array(arguments) => Ok(arguments),
+        argument => Ok(Vec::from([argument])),
+    }
+}
+
+/// Serializes a value into the arguments of a command, as described in
+/// [`to_arguments`](fn.to_arguments.html).
+pub fn to_command<T>(address_pattern: &str, value: &T) -> Result<Command, SerdeError>
+    where T: ?Sized + Serialize
+{
+    Ok(Command {
+        address_pattern: address_pattern.to_string(),
+        arguments: to_arguments(value)?,
+    })
+}
+
+/// Serializes a single value into an argument.
+struct ArgumentSerializer;
+
+impl ser::Serializer for ArgumentSerializer {
+    type Ok = Argument;
+    type Error = SerdeError;
+
+    type SerializeSeq = ArraySerializer;
+    type SerializeTuple = ArraySerializer;
+    type SerializeTupleStruct = ArraySerializer;
+    type SerializeTupleVariant = Impossible<Argument, SerdeError>;
+    type SerializeMap = Impossible<Argument, SerdeError>;
+    type SerializeStruct = ArraySerializer;
+    type SerializeStructVariant = Impossible<Argument, SerdeError>;
+
+    fn serialize_bool(self, v: bool) -> Result<Argument, SerdeError> {
+        Ok(Argument::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Argument, SerdeError> {
+        Ok(Argument::Integer(i32::from(v)))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Argument, SerdeError> {
+        Ok(Argument::Integer(i32::from(v)))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Argument, SerdeError> {
+        Ok(Argument::Integer(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Argument, SerdeError> {
+        Ok(Argument::Long(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Argument, SerdeError> {
+        Ok(Argument::Integer(i32::from(v)))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Argument, SerdeError> {
+        Ok(Argument::Integer(i32::from(v)))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Argument, SerdeError> {
+        Ok(Argument::Long(i64::from(v)))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Argument, SerdeError> {
+        if v > i64::MAX as u64 {
+            return Err(SerdeError::Unsupported("u64 larger than i64::MAX"));
+        }
+        Ok(Argument::Long(v as i64))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Argument, SerdeError> {
+        Ok(Argument::Float(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Argument, SerdeError> {
+        Ok(Argument::Double(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Argument, SerdeError> {
+        Ok(Argument::Char(v))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Argument, SerdeError> {
+        Ok(Argument::String(v.to_string()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Argument, SerdeError> {
+        Ok(Argument::Binary(v.to_vec()))
+    }
+
+    fn serialize_none(self) -> Result<Argument, SerdeError> {
+        Ok(Argument::Nil)
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Argument, SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Argument, SerdeError> {
+        Ok(Argument::Nil)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Argument, SerdeError> {
+        Ok(Argument::Nil)
+    }
+
+    fn serialize_unit_variant(self,
+                              _name: &'static str,
+                              _index: u32,
+                              variant: &'static str) -> Result<Argument, SerdeError> {
+        Ok(Argument::String(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T>(self,
+                                   _name: &'static str,
+                                   value: &T) -> Result<Argument, SerdeError>
+        where T: ?Sized + Serialize
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(self,
+                                    _name: &'static str,
+                                    _index: u32,
+                                    _variant: &'static str,
+                                    _value: &T) -> Result<Argument, SerdeError>
+        where T: ?Sized + Serialize
+    {
+        Err(SerdeError::Unsupported("enum variant with data"))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<ArraySerializer, SerdeError> {
+        Ok(ArraySerializer {
+            elements: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<ArraySerializer, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self,
+                              _name: &'static str,
+                              len: usize) -> Result<ArraySerializer, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(self,
+                               _name: &'static str,
+                               _index: u32,
+                               _variant: &'static str,
+                               _len: usize)
+                               -> Result<Impossible<Argument, SerdeError>, SerdeError> {
+        Err(SerdeError::Unsupported("enum variant with data"))
+    }
+
+    fn serialize_map(self,
+                     _len: Option<usize>)
+                     -> Result<Impossible<Argument, SerdeError>, SerdeError> {
+        Err(SerdeError::Unsupported("map"))
+    }
+
+    fn serialize_struct(self,
+                        _name: &'static str,
+                        len: usize) -> Result<ArraySerializer, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_struct_variant(self,
+                                _name: &'static str,
+                                _index: u32,
+                                _variant: &'static str,
+                                _len: usize)
+                                -> Result<Impossible<Argument, SerdeError>, SerdeError> {
+        Err(SerdeError::Unsupported("enum variant with data"))
+    }
+}
+
+/// Collects the elements of a compound value into an array argument.
+struct ArraySerializer {
+    elements: Vec<Argument>,
+}
+
+impl ArraySerializer {
+    fn push<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.elements.push(value.serialize(ArgumentSerializer)?);
+        Ok(())
+    }
+}
+
+impl ser::SerializeSeq for ArraySerializer {
+    type Ok = Argument;
+    type Error = SerdeError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Argument, SerdeError> {
+        Ok(Argument::Array(self.elements))
+    }
+}
+
+impl ser::SerializeTuple for ArraySerializer {
+    type Ok = Argument;
+    type Error = SerdeError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Argument, SerdeError> {
+        Ok(Argument::Array(self.elements))
+    }
+}
+
+impl ser::SerializeTupleStruct for ArraySerializer {
+    type Ok = Argument;
+    type Error = SerdeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Argument, SerdeError> {
+        Ok(Argument::Array(self.elements))
+    }
+}
+
+impl ser::SerializeStruct for ArraySerializer {
+    type Ok = Argument;
+    type Error = SerdeError;
+
+    fn serialize_field<T>(&mut self, _key: &'static str, value: &T) -> Result<(), SerdeError>
+        where T: ?Sized + Serialize
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Argument, SerdeError> {
+        Ok(Argument::Array(self.elements))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    struct Eq {
+        on: bool,
+        band: Band,
+    }
+
+    #[derive(Serialize)]
+    struct Band {
+        gain: f32,
+        frequency: f32,
+    }
+
+    #[derive(Serialize)]
+    enum Mode {
+        Off,
+        On,
+        Level(f32),
+    }
+
+    #[test]
+    fn writes_nested_structs_as_arrays() {
+        let eq = Eq { on: true, band: Band { gain: -3.0, frequency: 1000.0 } };
+        assert_eq!(to_arguments(&eq).unwrap(), [
+            Argument::Bool(true),
+            Argument::Array(Vec::from([Argument::Float(-3.0), Argument::Float(1000.0)])),
+        ]);
+        assert_eq!(to_arguments(&(1u8, [2u8, 3])).unwrap(), [
+            Argument::Integer(1),
+            Argument::Array(Vec::from([Argument::Integer(2), Argument::Integer(3)])),
+        ]);
+    }
+
+    #[test]
+    fn writes_none_as_nil() {
+        assert_eq!(to_arguments(&None::<i32>).unwrap(), [Argument::Nil]);
+        assert_eq!(to_arguments(&Some(3)).unwrap(), [Argument::Integer(3)]);
+        assert_eq!(to_arguments(&(Some("a"), None::<f32>)).unwrap(),
+                   [Argument::String("a".into()), Argument::Nil]);
+        assert_eq!(to_arguments(&()).unwrap(), [Argument::Nil]);
+    }
+
+    #[test]
+    fn writes_unit_variants_by_name() {
+        assert_eq!(to_arguments(&Mode::Off).unwrap(), [Argument::String("Off".into())]);
+        assert_eq!(to_arguments(&Mode::On).unwrap(), [Argument::String("On".into())]);
+        assert_eq!(to_arguments(&Mode::Level(0.5)),
+                   Err(SerdeError::Unsupported("enum variant with data")));
+    }
+
+    #[test]
+    fn writes_integers_in_the_narrowest_tag() {
+        assert_eq!(to_arguments(&-1i8).unwrap(), [Argument::Integer(-1)]);
+        assert_eq!(to_arguments(&65535u16).unwrap(), [Argument::Integer(65535)]);
+        assert_eq!(to_arguments(&i32::MIN).unwrap(), [Argument::Integer(i32::MIN)]);
+        assert_eq!(to_arguments(&u32::MAX).unwrap(), [Argument::Long(u32::MAX.into())]);
+        assert_eq!(to_arguments(&-1i64).unwrap(), [Argument::Long(-1)]);
+    }
+
+    #[test]
+    fn rejects_u64_too_large_for_a_long() {
+        assert_eq!(to_arguments(&(i64::MAX as u64)).unwrap(), [Argument::Long(i64::MAX)]);
+        let error = SerdeError::Unsupported("u64 larger than i64::MAX");
+        assert_eq!(to_arguments(&(i64::MAX as u64 + 1)), Err(error.clone()));
+        assert_eq!(to_arguments(&u64::MAX), Err(error));
+    }
+
+    #[test]
+    fn rejects_maps() {
+        let mut map = BTreeMap::new();
+        map.insert("a", 1);
+        assert_eq!(to_arguments(&map), Err(SerdeError::Unsupported("map")));
+    }
+
+    #[test]
+    fn writes_commands() {
+        let command = to_command("/ch/01/mix", &(true, 0.75f32)).unwrap();
+        assert_eq!(command, Command::new("/ch/01/mix").arg(true).arg(0.75));
+    }
+}
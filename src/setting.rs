@@ -0,0 +1,95 @@
+//! Querying and changing single mixer parameters, and converting their values to and from the
+//! mixer's range of 0.0 to 1.0.
+
+use std::io;
+
+use alloc::format;
+
+use address::Address;
+use argument::Argument;
+use client::MixerClient;
+
+/// Queries a parameter and returns the single argument of the reply.
+pub(crate) fn get(client: &mut MixerClient, address: Address) -> io::Result<Argument> {
+    let mut reply = client.request(&address.get())?;
+    if reply.arguments.len() != 1 {
+        let message = format!("unexpected reply: {}", reply);
+        return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+    }
+    Ok(reply.arguments.remove(0))
+}
+
+pub(crate) fn get_float(client: &mut MixerClient, address: Address) -> io::Result<f32> {
+    match get(client, address)? {
+        Argument::Float(value) => Ok(value),
+        argument => Err(unexpected(address, &argument)),
+    }
+}
+
+pub(crate) fn get_bool(client: &mut MixerClient, address: Address) -> io::Result<bool> {
+    match get(client, address)? {
+        Argument::Integer(0) => Ok(false),
+        Argument::Integer(1) => Ok(true),
+        argument => Err(unexpected(address, &argument)),
+    }
+}
+
+/// Returns a switch's setting as the mixer takes it, the integer 0 or 1.
+pub(crate) fn bool_argument(on: bool) -> Argument {
+    Argument::Integer(on as i32)
+}
+
+pub(crate) fn unexpected(address: Address, argument: &Argument) -> io::Error {
+    let message = format!("unexpected value for {}: {:?}", address, argument);
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+pub(crate) fn clamp_unit(value: f32) -> f32 {
+    if value.is_nan() {
+        0.0
+    } else {
+        value.clamp(0.0, 1.0)
+    }
+}
+
+/// Converts a value from 0.0 to 1.0 on a log scale to the quantity from `min` to `max`.
+pub(crate) fn from_log_scale(value: f32, min: f32, max: f32) -> f32 {
+    min * (max / min).powf(clamp_unit(value))
+}
+
+/// Converts a quantity from `min` to `max` to a value from 0.0 to 1.0 on a log scale.
+/// Quantities beyond either end give that end, so `min` may be the larger of the two.
+pub(crate) fn to_log_scale(quantity: f32, min: f32, max: f32) -> f32 {
+    // A quantity of zero or less has no logarithm, and lies beyond the lower of the two ends.
+    if quantity <= 0.0 {
+        return if min < max { 0.0 } else { 1.0 };
+    }
+    clamp_unit((quantity / min).ln() / (max / min).ln())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clamps_quantities_beyond_the_ends_of_a_log_scale() {
+        for &(min, max) in &[(20.0, 400.0), (10.0, 0.3)] {
+            assert_eq!(to_log_scale(min, min, max), 0.0);
+            assert_eq!(to_log_scale(max, min, max), 1.0);
+            assert_eq!(from_log_scale(-1.0, min, max), min);
+            assert_eq!(from_log_scale(2.0, min, max), max);
+        }
+        assert_eq!(to_log_scale(1000.0, 20.0, 400.0), 1.0);
+        assert_eq!(to_log_scale(20.0, 10.0, 0.3), 0.0);
+        assert_eq!(to_log_scale(0.1, 10.0, 0.3), 1.0);
+    }
+
+    #[test]
+    fn puts_quantities_of_zero_or_less_at_the_lower_end() {
+        for &quantity in &[0.0, -0.0, -1.0, f32::NEG_INFINITY] {
+            assert_eq!(to_log_scale(quantity, 20.0, 400.0), 0.0);
+            // When `min` is the larger end, the lower end is at 1.0.
+            assert_eq!(to_log_scale(quantity, 10.0, 0.3), 1.0);
+        }
+    }
+}
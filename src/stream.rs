@@ -0,0 +1,353 @@
+//! Carrying packets over byte streams such as TCP.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+use alloc::format;
+use alloc::vec::Vec;
+use byteorder::{BigEndian, ByteOrder};
+
+use bundle::Packet;
+use command::Command;
+
+/// The largest packet a frame can hold, 1 MiB. Larger frames are skipped and fail with
+/// `ErrorKind::InvalidData`, rather than being read into memory.
+pub const MAX_FRAME_SIZE: usize = 1 << 20;
+
+/// The SLIP byte that ends a frame.
+const END: u8 = 0xc0;
+/// The SLIP byte that escapes an `END` or `ESC` byte in a frame.
+const ESC: u8 = 0xdb;
+/// Follows `ESC` in place of an `END` byte.
+const ESC_END: u8 = 0xdc;
+/// Follows `ESC` in place of an `ESC` byte.
+const ESC_ESC: u8 = 0xdd;
+
+/// How packets are delimited on a byte stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framing {
+    /// Each packet is preceded by its size as a big-endian 32-bit integer, as in OSC 1.0.
+    LengthPrefix,
+    /// Each packet is SLIP encoded, with an `END` byte both before and after it, as in OSC 1.1.
+    Slip,
+}
+
+/// Sends and receives packets over a byte stream, such as a `TcpStream`.
+///
+/// Reads are buffered, so the stream should not be read from except through this type.
+///
+/// ```
+/// use std::net::{TcpListener, TcpStream};
+/// use std::thread;
+///
+/// use april_2018_challenge::{Command, FramedStream, Framing, Packet};
+///
+/// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+/// let addr = listener.local_addr().unwrap();
+/// let peer = thread::spawn(move || {
+///     let (stream, _) = listener.accept().unwrap();
+///     let mut stream = FramedStream::new(stream, Framing::Slip);
+///     let packet = stream.recv().unwrap();
+///     stream.send_packet(&packet).unwrap();
+/// });
+///
+/// let command = Command::new("/ch/01/mix/fader").arg(0.75);
+/// let mut stream = FramedStream::new(TcpStream::connect(addr).unwrap(), Framing::Slip);
+/// stream.send(&command).unwrap();
+/// assert_eq!(stream.recv().unwrap(), Packet::Command(command));
+/// peer.join().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct FramedStream<S> {
+    reader: BufReader<S>,
+    framing: Framing,
+    /// The most recently read frame.
+    frame: Vec<u8>,
+    /// The packet being sent.
+    packet: Vec<u8>,
+    /// The frame being sent or received, before it is unwrapped.
+    out: Vec<u8>,
+}
+
+impl<S: Read> FramedStream<S> {
+
+    /// Wraps a stream, delimiting packets with `framing`.
+    pub fn new(stream: S, framing: Framing) -> FramedStream<S> {
+        FramedStream {
+            reader: BufReader::new(stream),
+            framing,
+            frame: Vec::new(),
+            packet: Vec::new(),
+            out: Vec::new(),
+        }
+    }
+
+    /// Receives a command or a bundle.
+    ///
+    /// A frame that does not hold a valid packet fails with `ErrorKind::InvalidData`, and the
+    /// next call reads the frame after it.
+    pub fn recv(&mut self) -> io::Result<Packet> {
+        let frame = self.read_frame()?;
+        Packet::decode(frame).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Receives the next frame without decoding it.
+    ///
+    /// Fails with `ErrorKind::UnexpectedEof` if the stream ends before a whole frame is read,
+    /// and with `ErrorKind::InvalidData` if the frame holds more than
+    /// [`MAX_FRAME_SIZE`](constant.MAX_FRAME_SIZE.html) bytes, in which case the next call
+    /// reads the frame after it.
+    pub fn read_frame(&mut self) -> io::Result<&[u8]> {
+        match self.framing {
+            Framing::LengthPrefix => self.read_length_prefixed()?,
+            Framing::Slip => self.read_slip()?,
+        }
+        Ok(&self.frame)
+    }
+
+    fn read_length_prefixed(&mut self) -> io::Result<()> {
+        let mut size = [0; 4];
+        self.reader.read_exact(&mut size)?;
+        let len = BigEndian::read_u32(&size) as u64;
+
+        self.frame.clear();
+        if len > MAX_FRAME_SIZE as u64 {
+            // Skip the frame without keeping it, so the next one can still be read.
+            if io::copy(&mut (&mut self.reader).take(len), &mut io::sink())? != len {
+                return Err(unexpected_eof());
+            }
+            return Err(too_large());
+        }
+        // Read through `take` rather than allocating `len` bytes up front, so that a bogus size
+        // fails at the end of the stream instead of allocating memory that is never filled.
+        if (&mut self.reader).take(len).read_to_end(&mut self.frame)? as u64 != len {
+            return Err(unexpected_eof());
+        }
+        Ok(())
+    }
+
+    fn read_slip(&mut self) -> io::Result<()> {
+        loop {
+            self.read_until_end()?;
+            // The END that starts a frame also ends an empty one, which is skipped.
+            if !self.out.is_empty() {
+                break;
+            }
+        }
+
+        self.frame.clear();
+        let mut bytes = self.out.iter();
+        while let Some(&byte) = bytes.next() {
+            let byte = match byte {
+                ESC => match bytes.next() {
+                    Some(&ESC_END) => END,
+                    Some(&ESC_ESC) => ESC,
+                    _ => {
+                        let message = "invalid SLIP escape sequence";
+                        return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+                    },
+                },
+                _ => byte,
+            };
+            self.frame.push(byte);
+        }
+        if self.frame.len() > MAX_FRAME_SIZE {
+            self.frame.clear();
+            return Err(too_large());
+        }
+        Ok(())
+    }
+
+    /// Reads the bytes up to the next `END` into `out`, and skips the `END`.
+    ///
+    /// A frame whose escaped bytes could not fit in `MAX_FRAME_SIZE` once unescaped is skipped
+    /// up to its `END` without being kept, and fails with `ErrorKind::InvalidData`.
+    fn read_until_end(&mut self) -> io::Result<()> {
+        self.out.clear();
+        let mut skipping = false;
+        loop {
+            let (found, used) = {
+                let available = self.reader.fill_buf()?;
+                if available.is_empty() {
+                    return Err(unexpected_eof());
+                }
+                let (bytes, found) = match available.iter().position(|&byte| byte == END) {
+                    Some(end) => (&available[..end], true),
+                    None => (available, false),
+                };
+                if !skipping {
+                    self.out.extend_from_slice(bytes);
+                }
+                (found, bytes.len() + found as usize)
+            };
+            self.reader.consume(used);
+
+            // Every byte of a frame takes at most two bytes once escaped.
+            if self.out.len() > 2 * MAX_FRAME_SIZE {
+                skipping = true;
+                self.out.clear();
+            }
+            if found {
+                return if skipping { Err(too_large()) } else { Ok(()) };
+            }
+        }
+    }
+}
+
+impl<S> FramedStream<S> {
+
+    /// Returns the framing used to delimit packets.
+    pub fn framing(&self) -> Framing {
+        self.framing
+    }
+
+    /// Returns a reference to the underlying stream.
+    pub fn get_ref(&self) -> &S {
+        self.reader.get_ref()
+    }
+
+    /// Returns a mutable reference to the underlying stream.
+    pub fn get_mut(&mut self) -> &mut S {
+        self.reader.get_mut()
+    }
+
+    /// Unwraps the underlying stream. Any data that has been read from it but not yet returned
+    /// as a frame is lost.
+    pub fn into_inner(self) -> S {
+        self.reader.into_inner()
+    }
+}
+
+impl<S: Write> FramedStream<S> {
+
+    /// Sends a command.
+    pub fn send(&mut self, command: &Command) -> io::Result<()> {
+        self.packet.clear();
+        command.encode(&mut self.packet);
+        frame(self.framing, &self.packet, &mut self.out);
+        self.write_out()
+    }
+
+    /// Sends a command or a bundle.
+    pub fn send_packet(&mut self, packet: &Packet) -> io::Result<()> {
+        self.packet.clear();
+        packet.encode(&mut self.packet);
+        frame(self.framing, &self.packet, &mut self.out);
+        self.write_out()
+    }
+
+    /// Sends an already encoded packet as a single frame.
+    pub fn write_frame(&mut self, packet: &[u8]) -> io::Result<()> {
+        frame(self.framing, packet, &mut self.out);
+        self.write_out()
+    }
+
+    /// Writes out a whole frame in one go and flushes the stream.
+    fn write_out(&mut self) -> io::Result<()> {
+        let stream = self.reader.get_mut();
+        stream.write_all(&self.out)?;
+        stream.flush()
+    }
+}
+
+/// Wraps an encoded packet in a frame.
+fn frame(framing: Framing, packet: &[u8], out: &mut Vec<u8>) {
+    out.clear();
+    match framing {
+        Framing::LengthPrefix => {
+            let mut size = [0; 4];
+            BigEndian::write_u32(&mut size, packet.len() as u32);
+            out.extend(&size);
+            out.extend(packet);
+        },
+        Framing::Slip => {
+            out.push(END);
+            for &byte in packet {
+                match byte {
+                    END => out.extend(&[ESC, ESC_END]),
+                    ESC => out.extend(&[ESC, ESC_ESC]),
+                    _ => out.push(byte),
+                }
+            }
+            out.push(END);
+        },
+    }
+}
+
+fn too_large() -> io::Error {
+    let message = format!("frame holds more than {} bytes", MAX_FRAME_SIZE);
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn unexpected_eof() -> io::Error {
+    io::Error::new(io::ErrorKind::UnexpectedEof, "stream ended in the middle of a frame")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+    use std::vec;
+
+    /// Sends a command to a peer that echoes it back over TCP, with the given framing.
+    fn echo(framing: Framing) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let peer = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut stream = FramedStream::new(stream, framing);
+            let packet = stream.recv().unwrap();
+            stream.send_packet(&packet).unwrap();
+        });
+
+        let command = Command::new("/ch/01/config/name").arg("Vocals").arg(0.75);
+        let mut stream = FramedStream::new(TcpStream::connect(addr).unwrap(), framing);
+        stream.send(&command).unwrap();
+        assert_eq!(stream.recv().unwrap(), Packet::Command(command));
+        peer.join().unwrap();
+    }
+
+    #[test]
+    fn length_prefix_loopback() {
+        echo(Framing::LengthPrefix);
+    }
+
+    #[test]
+    fn slip_loopback() {
+        echo(Framing::Slip);
+    }
+
+    /// Returns a stream holding `frame`, framed with `framing`, then a command.
+    fn after(framing: Framing, frame: &[u8]) -> FramedStream<Cursor<Vec<u8>>> {
+        let mut stream = FramedStream::new(Cursor::new(Vec::new()), framing);
+        stream.write_frame(frame).unwrap();
+        stream.send(&Command::new("/next")).unwrap();
+        stream.get_mut().set_position(0);
+        stream
+    }
+
+    #[test]
+    fn skips_frames_that_are_too_large() {
+        for &framing in &[Framing::LengthPrefix, Framing::Slip] {
+            let mut stream = after(framing, &vec![END; MAX_FRAME_SIZE + 1]);
+            assert_eq!(stream.recv().unwrap_err().kind(), io::ErrorKind::InvalidData);
+            assert_eq!(stream.recv().unwrap(), Packet::Command(Command::new("/next")));
+
+            let mut stream = after(framing, &vec![0; MAX_FRAME_SIZE]);
+            assert_eq!(stream.read_frame().unwrap().len(), MAX_FRAME_SIZE);
+            assert_eq!(stream.recv().unwrap(), Packet::Command(Command::new("/next")));
+        }
+    }
+
+    #[test]
+    fn fails_when_a_large_frame_is_cut_short() {
+        let mut stream = FramedStream::new(Cursor::new(Vec::from(&b"\xff\xff\xff\xff/a"[..])),
+                                           Framing::LengthPrefix);
+        assert_eq!(stream.recv().unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+
+        let mut stream = FramedStream::new(Cursor::new(vec![b'a'; 3 * MAX_FRAME_SIZE]),
+                                           Framing::Slip);
+        assert_eq!(stream.recv().unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,403 @@
+//! Keeping subscriptions to updates and meters pushed by the mixer alive.
+
+use std::collections::VecDeque;
+use std::io;
+use std::net::UdpSocket;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use client::MixerClient;
+use command::Command;
+use error::AddressError;
+
+/// How often `/xremote` is sent. The mixer stops pushing changes 10 seconds after the last one.
+pub const REMOTE_INTERVAL: Duration = Duration::from_secs(9);
+/// How often each `/meters` command is sent. The mixer stops sending meters 10 seconds after
+/// the last one.
+pub const METERS_INTERVAL: Duration = Duration::from_secs(9);
+/// The highest numbered bank of meters.
+const MAX_METER_BANK: u8 = 16;
+
+/// Sends a command to the mixer from a background thread, once straight away and then every
+/// `interval`, until it is dropped.
+///
+/// The thread sends on a clone of the socket, so the socket can still be used to receive what
+/// the mixer pushes. Errors sending are ignored, and the command is sent again next time.
+#[derive(Debug)]
+pub struct KeepAlive {
+    /// Dropped to tell the thread to stop.
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl KeepAlive {
+
+    /// Starts sending `command` on `socket`, which must be connected to the mixer.
+    pub fn start(socket: &UdpSocket,
+                 command: &Command,
+                 interval: Duration) -> io::Result<KeepAlive> {
+        let socket = socket.try_clone()?;
+        let mut packet = Vec::new();
+        command.encode(&mut packet);
+
+        let (stop, stopped) = mpsc::channel::<()>();
+        let thread = thread::Builder::new()
+            .name("osc-keep-alive".into())
+            .spawn(move || loop {
+                let _ = socket.send(&packet);
+                match stopped.recv_timeout(interval) {
+                    Err(RecvTimeoutError::Timeout) => {},
+                    Ok(()) | Err(RecvTimeoutError::Disconnected) => break,
+                }
+            })?;
+        Ok(KeepAlive { stop: Some(stop), thread: Some(thread) })
+    }
+}
+
+impl Drop for KeepAlive {
+    /// Stops the thread and waits for it to finish.
+    fn drop(&mut self) {
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// The changes that others make to the mixer, which it pushes to clients that have sent
+/// `/xremote` within the last 10 seconds. Created by
+/// [`MixerClient::remote`](struct.MixerClient.html#method.remote).
+///
+/// Iterating waits for the next command the mixer pushes, with commands in a bundle returned
+/// one at a time, and never ends. Read timeouts are waited out; other errors are returned, and
+/// iterating again carries on with the next packet. Replies to queries made through
+/// [`client`](#method.client) while subscribed are not returned.
+///
+/// `/xremote` stops being sent when this is dropped.
+///
+/// ```
+/// use std::net::UdpSocket;
+/// use std::thread;
+///
+/// use april_2018_challenge::{Command, MixerClient};
+///
+/// // A stand-in for a mixer, on which someone moves a fader.
+/// let mixer = UdpSocket::bind("127.0.0.1:0").unwrap();
+/// let addr = mixer.local_addr().unwrap();
+/// thread::spawn(move || {
+///     let mut buf = [0; 512];
+///     let (len, from) = mixer.recv_from(&mut buf).unwrap();
+///     assert_eq!(Command::decode(&buf[..len]).unwrap(), Command::new("/xremote"));
+///     let mut buf = Vec::new();
+///     Command::new("/ch/01/mix/fader").arg(0.5).encode(&mut buf);
+///     mixer.send_to(&buf, from).unwrap();
+/// });
+///
+/// let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+/// socket.connect(addr).unwrap();
+/// let mut client = MixerClient::new(socket);
+/// let mut remote = client.remote().unwrap();
+/// let change = remote.next().unwrap().unwrap();
+/// assert_eq!(change, Command::new("/ch/01/mix/fader").arg(0.5));
+/// ```
+#[derive(Debug)]
+pub struct Remote<'a> {
+    updates: Updates<'a>,
+    _keep_alive: KeepAlive,
+}
+
+impl<'a> Remote<'a> {
+
+    pub(crate) fn new(client: &'a mut MixerClient) -> io::Result<Remote<'a>> {
+        let keep_alive = KeepAlive::start(client.get_ref(),
+                                          &Command::new("/xremote"),
+                                          REMOTE_INTERVAL)?;
+        Ok(Remote { updates: Updates::new(client), _keep_alive: keep_alive })
+    }
+
+    /// Returns the client, to query or change the mixer while subscribed.
+    pub fn client(&mut self) -> &mut MixerClient {
+        self.updates.client
+    }
+}
+
+impl<'a> Iterator for Remote<'a> {
+    type Item = io::Result<Command>;
+
+    fn next(&mut self) -> Option<io::Result<Command>> {
+        Some(self.updates.next())
+    }
+}
+
+/// A bank of meters to subscribe to with
+/// [`MixerClient::meters`](struct.MixerClient.html#method.meters), from `/meters/0` to
+/// `/meters/16`.
+///
+/// Some banks take further integer arguments, such as the channel to meter, and most take a
+/// time factor that sets how often the mixer sends them, in units of 50 ms.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MeterBank {
+    bank: u8,
+    arguments: Vec<i32>,
+}
+
+impl MeterBank {
+
+    /// Creates a subscription to `/meters/<bank>`, with no further arguments.
+    ///
+    /// Fails with `AddressError::OutOfRange` if `bank` is greater than 16.
+    ///
+    /// ```
+    /// use april_2018_challenge::MeterBank;
+    ///
+    /// assert_eq!(MeterBank::new(16).unwrap().address(), "/meters/16");
+    /// assert!(MeterBank::new(17).is_err());
+    /// ```
+    pub fn new(bank: u8) -> Result<MeterBank, AddressError> {
+        if bank > MAX_METER_BANK {
+            return Err(AddressError::OutOfRange {
+                kind: "meter bank",
+                number: bank,
+                min: 0,
+                max: MAX_METER_BANK,
+            });
+        }
+        Ok(MeterBank { bank, arguments: Vec::new() })
+    }
+
+    /// Adds an integer argument to the subscription, such as a channel number.
+    pub fn arg(mut self, argument: i32) -> MeterBank {
+        self.arguments.push(argument);
+        self
+    }
+
+    /// Adds a time factor argument, asking for the meters every `factor` × 50 ms. It must come
+    /// after any other arguments.
+    pub fn time_factor(self, factor: u8) -> MeterBank {
+        self.arg(factor.into())
+    }
+
+    /// Returns the bank's number.
+    pub fn bank(&self) -> u8 {
+        self.bank
+    }
+
+    /// Returns the address the mixer sends the bank's meters to, such as `/meters/1`.
+    pub fn address(&self) -> String {
+        format!("/meters/{}", self.bank)
+    }
+
+    /// Returns the `/meters` command that subscribes to the bank.
+    pub fn command(&self) -> Command {
+        let command = Command::new("/meters").arg(self.address());
+        self.arguments.iter().fold(command, |command, &argument| command.arg(argument))
+    }
+}
+
+/// A subscription to one or more banks of meters, created by
+/// [`MixerClient::meters`](struct.MixerClient.html#method.meters).
+///
+/// The mixer only sends meters for 10 seconds after it is asked, so each bank's `/meters`
+/// command is sent again every [`METERS_INTERVAL`](constant.METERS_INTERVAL.html) from a
+/// background thread. When this is dropped, the commands stop and `/unsubscribe` is sent.
+///
+/// Iterating waits for the next meters command from one of the subscribed banks, and never
+/// ends. Anything else the mixer sends is discarded. Read timeouts are waited out; other errors
+/// are returned, and iterating again carries on with the next packet.
+///
+/// ```
+/// use std::net::UdpSocket;
+/// use std::thread;
+///
+/// use april_2018_challenge::{Argument, Command, MeterBank, MixerClient};
+///
+/// // A stand-in for a mixer, which sends one update for each subscription.
+/// let mixer = UdpSocket::bind("127.0.0.1:0").unwrap();
+/// let addr = mixer.local_addr().unwrap();
+/// thread::spawn(move || {
+///     let mut buf = [0; 512];
+///     loop {
+///         let (len, from) = mixer.recv_from(&mut buf).unwrap();
+///         let request = Command::decode(&buf[..len]).unwrap();
+///         if request.address_pattern == "/unsubscribe" {
+///             break;
+///         }
+///         let bank = match request.arguments[0] {
+///             Argument::String(ref bank) => bank.clone(),
+///             _ => panic!("expected a meter bank"),
+///         };
+///         let mut reply = Vec::new();
+///         Command::new(bank).arg(vec![0u8; 8]).encode(&mut reply);
+///         mixer.send_to(&reply, from).unwrap();
+///     }
+/// });
+///
+/// let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+/// socket.connect(addr).unwrap();
+/// let mut client = MixerClient::new(socket);
+/// let banks = [MeterBank::new(1).unwrap(), MeterBank::new(2).unwrap().time_factor(2)];
+/// let mut meters = client.meters(&banks).unwrap();
+/// let mut banks = vec![
+///     meters.next().unwrap().unwrap().address_pattern,
+///     meters.next().unwrap().unwrap().address_pattern,
+/// ];
+/// banks.sort();
+/// assert_eq!(banks, ["/meters/1", "/meters/2"]);
+/// ```
+#[derive(Debug)]
+pub struct Meters<'a> {
+    updates: Updates<'a>,
+    /// The addresses the subscribed banks are sent to.
+    addresses: Vec<String>,
+    keep_alives: Vec<KeepAlive>,
+}
+
+impl<'a> Meters<'a> {
+
+    pub(crate) fn new(client: &'a mut MixerClient, banks: &[MeterBank]) -> io::Result<Meters<'a>> {
+        let keep_alives = banks.iter()
+            .map(|bank| KeepAlive::start(client.get_ref(), &bank.command(), METERS_INTERVAL))
+            .collect::<io::Result<Vec<_>>>()?;
+        Ok(Meters {
+            updates: Updates::new(client),
+            addresses: banks.iter().map(MeterBank::address).collect(),
+            keep_alives,
+        })
+    }
+
+    /// Returns the client, to query or change the mixer while subscribed.
+    pub fn client(&mut self) -> &mut MixerClient {
+        self.updates.client
+    }
+}
+
+impl<'a> Iterator for Meters<'a> {
+    type Item = io::Result<Command>;
+
+    fn next(&mut self) -> Option<io::Result<Command>> {
+        loop {
+            match self.updates.next() {
+                Ok(command) => {
+                    if self.addresses.contains(&command.address_pattern) {
+                        return Some(Ok(command));
+                    }
+                },
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+impl<'a> Drop for Meters<'a> {
+    /// Stops renewing the subscriptions and cancels them.
+    fn drop(&mut self) {
+        self.keep_alives.clear();
+        let _ = self.updates.client.send(&Command::new("/unsubscribe"));
+    }
+}
+
+/// The commands pushed to a client, read one at a time.
+#[derive(Debug)]
+struct Updates<'a> {
+    client: &'a mut MixerClient,
+    /// Commands from a bundle that have not been returned yet.
+    pending: VecDeque<Command>,
+}
+
+impl<'a> Updates<'a> {
+
+    fn new(client: &'a mut MixerClient) -> Updates<'a> {
+        Updates { client, pending: VecDeque::new() }
+    }
+
+    /// Waits for the next command, returning any error other than a read timeout.
+    fn next(&mut self) -> io::Result<Command> {
+        loop {
+            if let Some(command) = self.pending.pop_front() {
+                return Ok(command);
+            }
+            match self.client.recv() {
+                Ok(packet) => self.pending.extend(packet.commands().cloned()),
+                Err(ref e) if e.kind() == io::ErrorKind::TimedOut => {},
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use std::time::Instant;
+
+    use emulator;
+
+    #[test]
+    fn rejects_meter_banks_out_of_range() {
+        assert_eq!(MeterBank::new(0).unwrap().address(), "/meters/0");
+        assert_eq!(MeterBank::new(16).unwrap().address(), "/meters/16");
+        let error = MeterBank::new(17).unwrap_err();
+        assert_eq!(error, AddressError::OutOfRange {
+            kind: "meter bank",
+            number: 17,
+            min: 0,
+            max: MAX_METER_BANK,
+        });
+        assert_eq!(error.to_string(), "there is no meter bank 17; they are numbered 0 to 16");
+        assert!(MeterBank::new(255).is_err());
+    }
+
+    #[test]
+    fn renews_subscriptions_until_dropped() {
+        let mixer = UdpSocket::bind("127.0.0.1:0").unwrap();
+        mixer.set_read_timeout(Some(Duration::from_millis(500))).unwrap();
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket.connect(mixer.local_addr().unwrap()).unwrap();
+
+        let command = MeterBank::new(1).unwrap().time_factor(2).command();
+        let interval = Duration::from_millis(20);
+        let start = Instant::now();
+        let keep_alive = KeepAlive::start(&socket, &command, interval).unwrap();
+        let mut buf = [0; 512];
+        for _ in 0..3 {
+            let len = mixer.recv(&mut buf).unwrap();
+            assert_eq!(Command::decode(&buf[..len]).unwrap(), command);
+        }
+        // Sent straight away, then after each interval.
+        assert!(start.elapsed() >= interval * 2);
+
+        drop(keep_alive);
+        // At most one send can have been on its way when the keep-alive was dropped.
+        let sent_after = (0..2).take_while(|_| mixer.recv(&mut buf).is_ok()).count();
+        assert!(sent_after <= 1);
+    }
+
+    #[test]
+    fn unsubscribes_when_dropped() {
+        let mut client = emulator::start();
+        client.set_timeout(Duration::from_millis(200));
+        {
+            let mut meters = client.meters(&[MeterBank::new(1).unwrap()]).unwrap();
+            let command = meters.next().unwrap().unwrap();
+            assert_eq!(command.address_pattern, "/meters/1");
+        }
+
+        // Meters already sent may still arrive, but the emulator sends them every 50 ms while
+        // subscribed, so a read only times out once they have stopped.
+        let mut arrived = 0;
+        let error = loop {
+            match client.recv() {
+                Ok(_) if arrived < 10 => arrived += 1,
+                Ok(packet) => panic!("meters still arriving after unsubscribing: {:?}", packet),
+                Err(e) => break e,
+            }
+        };
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+    }
+}
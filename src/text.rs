@@ -0,0 +1,500 @@
+//! A human-readable text form of commands, in the style of `oscsend`.
+
+use core::fmt::{self, Write};
+use core::str::{self, CharIndices, FromStr};
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use argument::{Argument, Midi, Rgba};
+use command::Command;
+use error::ParseError;
+use timetag::Timetag;
+use MAX_ARRAY_DEPTH;
+
+/// Prints the command in its text form: the address pattern, then the type tag string and one
+/// value per argument, all separated by spaces. A command with no arguments prints as just its
+/// address pattern.
+///
+/// | Type tag           | Value                                                |
+/// |--------------------|------------------------------------------------------|
+/// | `i`, `h`           | a decimal integer: `-42`                             |
+/// | `f`, `d`           | a decimal float: `0.75`, `1e-6`, `inf`, `NaN`        |
+/// | `s`, `S`           | a double-quoted string: `"say \"hi\""`               |
+/// | `c`                | a single-quoted character: `'x'`                     |
+/// | `b`                | `0x` and the bytes in hex: `0x00ff10`                |
+/// | `t`                | the seconds and fraction in hex: `00000000.00000001` |
+/// | `r`                | `#` and the color in hex: `#ff8000ff`                |
+/// | `m`                | `0x` and the 4 bytes in hex: `0x00904040`            |
+/// | `T`, `F`, `N`, `I` | no value                                             |
+/// | `[`, `]`           | no value; the elements' values follow in order       |
+///
+/// Strings and characters escape `\`, the quote, newline (`\n`), carriage return (`\r`) and tab
+/// (`\t`) with a backslash. An address pattern that contains whitespace is printed as a quoted
+/// string in the same way.
+///
+/// Parsing the text gives back an equal command as long as the address pattern starts with `/`,
+/// though a float or double that is NaN never compares equal to the one parsed back.
+///
+/// ```
+/// use april_2018_challenge::Command;
+///
+/// let command = Command::new("/meters").arg("/meters/1");
+/// assert_eq!(command.to_string(), r#"/meters ,s "/meters/1""#);
+/// ```
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.address_pattern.contains(char::is_whitespace) {
+            write_quoted(&self.address_pattern, '"', f)?;
+        } else {
+            f.write_str(&self.address_pattern)?;
+        }
+        if self.arguments.is_empty() {
+            return Ok(());
+        }
+        f.write_str(" ,")?;
+        for argument in &self.arguments {
+            write_tags(argument, f)?;
+        }
+        for argument in &self.arguments {
+            write_values(argument, f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a command from the text form printed by its `Display` impl.
+///
+/// Any amount of whitespace may separate the address pattern, the type tag string and the
+/// values, and the type tag string may be left out of a command with no arguments. The address
+/// pattern may be a quoted string, so that it can contain whitespace. Arrays may be nested up to
+/// [`MAX_ARRAY_DEPTH`](constant.MAX_ARRAY_DEPTH.html) deep.
+///
+/// ```
+/// use april_2018_challenge::{Argument, Command};
+///
+/// let command: Command = "/ch/01/mix/fader ,f 0.75".parse().unwrap();
+/// assert_eq!(command.address_pattern, "/ch/01/mix/fader");
+/// assert_eq!(command.arguments, [Argument::Float(0.75)]);
+/// ```
+impl FromStr for Command {
+    type Err = ParseError;
+
+    fn from_str(text: &str) -> Result<Command, ParseError> {
+        let mut parser = Parser { text, pos: 0 };
+        parser.skip_whitespace();
+        let offset = parser.pos;
+        let missing = ParseError::MissingAddress { offset };
+        let address_pattern = if text[offset..].starts_with('"') {
+            parser.quoted('"', missing.clone())?
+        } else {
+            parser.token().1.to_string()
+        };
+        if !address_pattern.starts_with('/') {
+            return Err(missing);
+        }
+        let mut command = Command::new(address_pattern);
+
+        parser.skip_whitespace();
+        if parser.at_end() {
+            return Ok(command);
+        }
+        let (offset, tags) = parser.token();
+        if !tags.starts_with(',') {
+            return Err(ParseError::MissingComma { offset });
+        }
+        command.arguments = parser.arguments(&mut tags[1..].char_indices(), offset + 1, None, 0)?;
+
+        parser.skip_whitespace();
+        if !parser.at_end() {
+            return Err(ParseError::TrailingText { offset: parser.pos });
+        }
+        Ok(command)
+    }
+}
+
+/// Writes the type tags of an argument.
+fn write_tags(argument: &Argument, f: &mut fmt::Formatter) -> fmt::Result {
+    match *argument {
+        Argument::Array(ref elements) => {
+            f.write_char('[')?;
+            for element in elements {
+                write_tags(element, f)?;
+            }
+            f.write_char(']')
+        },
+        _ => f.write_char(argument.tag() as char),
+    }
+}
+
+/// Writes the values of an argument, each preceded by a space.
+fn write_values(argument: &Argument, f: &mut fmt::Formatter) -> fmt::Result {
+    match *argument {
+        Argument::String(ref s) | Argument::Symbol(ref s) => {
+            f.write_char(' ')?;
+            write_quoted(s, '"', f)
+        },
+        Argument::Integer(i) => write!(f, " {}", i),
+        Argument::Float(x) => write!(f, " {:?}", x),
+        Argument::Binary(ref b) => {
+            f.write_str(" 0x")?;
+            for byte in b {
+                write!(f, "{:02x}", byte)?;
+            }
+            Ok(())
+        },
+        Argument::Long(h) => write!(f, " {}", h),
+        Argument::Double(x) => write!(f, " {:?}", x),
+        Argument::Timetag(t) => write!(f, " {:08x}.{:08x}", t.seconds, t.fraction),
+        Argument::Char(c) => {
+            f.write_char(' ')?;
+            write_quoted(c.encode_utf8(&mut [0; 4]), '\'', f)
+        },
+        Argument::Color(c) => {
+            write!(f, " #{:02x}{:02x}{:02x}{:02x}", c.red, c.green, c.blue, c.alpha)
+        },
+        Argument::Midi(m) => {
+            write!(f, " 0x{:02x}{:02x}{:02x}{:02x}", m.port, m.status, m.data1, m.data2)
+        },
+        Argument::Bool(_) | Argument::Nil | Argument::Infinitum => Ok(()),
+        Argument::Array(ref elements) => {
+            for element in elements {
+                write_values(element, f)?;
+            }
+            Ok(())
+        },
+    }
+}
+
+/// Writes a string between quotes, escaping it as needed.
+fn write_quoted(s: &str, quote: char, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_char(quote)?;
+    for c in s.chars() {
+        match c {
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if c == quote => {
+                f.write_char('\\')?;
+                f.write_char(c)?;
+            },
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char(quote)
+}
+
+/// Reads the parts of a command's text form, tracking the byte offset for errors.
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+
+    fn at_end(&self) -> bool {
+        self.pos == self.text.len()
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.pos = self.text.len() - self.text[self.pos..].trim_start().len();
+    }
+
+    /// Reads up to the next whitespace, returning the offset and the text read.
+    fn token(&mut self) -> (usize, &'a str) {
+        let start = self.pos;
+        self.pos = self.text[start..]
+            .find(char::is_whitespace)
+            .map_or(self.text.len(), |len| start + len);
+        (start, &self.text[start..self.pos])
+    }
+
+    /// Reads the values for a list of type tags, up to the `]` closing the array opened at
+    /// `open`, if any. `base` is the offset of the type tags in the text, and `depth` the number
+    /// of arrays the list is inside.
+    fn arguments(&mut self,
+                 tags: &mut CharIndices,
+                 base: usize,
+                 open: Option<usize>,
+                 depth: usize) -> Result<Vec<Argument>, ParseError> {
+        let mut arguments = Vec::new();
+        while let Some((i, tag)) = tags.next() {
+            let offset = base + i;
+            let argument = match tag {
+                '[' if depth == MAX_ARRAY_DEPTH => return Err(ParseError::TooDeep { offset }),
+                '[' => Argument::Array(self.arguments(tags, base, Some(offset), depth + 1)?),
+                ']' if open.is_some() => return Ok(arguments),
+                ']' => return Err(ParseError::UnbalancedArray { offset }),
+                _ => self.value(tag, offset)?,
+            };
+            arguments.push(argument);
+        }
+        match open {
+            Some(offset) => Err(ParseError::UnbalancedArray { offset }),
+            None => Ok(arguments),
+        }
+    }
+
+    /// Reads the value for a type tag at `tag_offset`.
+    fn value(&mut self, tag: char, tag_offset: usize) -> Result<Argument, ParseError> {
+        match tag {
+            'T' => return Ok(Argument::Bool(true)),
+            'F' => return Ok(Argument::Bool(false)),
+            'N' => return Ok(Argument::Nil),
+            'I' => return Ok(Argument::Infinitum),
+            's' | 'S' | 'c' | 'i' | 'h' | 'f' | 'd' | 'b' | 't' | 'r' | 'm' => {},
+            _ => return Err(ParseError::UnknownTag { offset: tag_offset, tag }),
+        }
+
+        self.skip_whitespace();
+        let offset = self.pos;
+        if self.at_end() {
+            return Err(ParseError::MissingValue { offset });
+        }
+        let invalid = ParseError::InvalidValue { offset, tag };
+        match tag {
+            's' => Ok(Argument::String(self.quoted('"', invalid)?)),
+            'S' => Ok(Argument::Symbol(self.quoted('"', invalid)?)),
+            'c' => {
+                let s = self.quoted('\'', invalid.clone())?;
+                let mut chars = s.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Ok(Argument::Char(c)),
+                    _ => Err(invalid),
+                }
+            },
+            _ => parse_token(tag, self.token().1).ok_or(invalid),
+        }
+    }
+
+    /// Reads a quoted string, undoing its escapes. Fails with `invalid` if the text does not start
+    /// with the quote, has an unknown escape, or goes on past the closing quote.
+    fn quoted(&mut self, quote: char, invalid: ParseError) -> Result<String, ParseError> {
+        let start = self.pos;
+        let mut chars = self.text[start..].char_indices();
+        if chars.next().map(|(_, c)| c) != Some(quote) {
+            return Err(invalid);
+        }
+
+        let mut value = String::new();
+        while let Some((i, c)) = chars.next() {
+            let c = match c {
+                c if c == quote => {
+                    self.pos = start + i + c.len_utf8();
+                    return match self.text[self.pos..].chars().next() {
+                        Some(c) if !c.is_whitespace() => Err(invalid),
+                        _ => Ok(value),
+                    };
+                },
+                '\\' => match chars.next() {
+                    Some((_, 'n')) => '\n',
+                    Some((_, 'r')) => '\r',
+                    Some((_, 't')) => '\t',
+                    Some((_, c)) if c == '\\' || c == '"' || c == '\'' => c,
+                    Some(_) => return Err(invalid),
+                    None => break,
+                },
+                c => c,
+            };
+            value.push(c);
+        }
+        Err(ParseError::UnterminatedQuote { offset: start })
+    }
+}
+
+/// Parses an unquoted value for a type tag.
+fn parse_token(tag: char, token: &str) -> Option<Argument> {
+    match tag {
+        'i' => token.parse().ok().map(Argument::Integer),
+        'h' => token.parse().ok().map(Argument::Long),
+        'f' => token.parse().ok().map(Argument::Float),
+        'd' => token.parse().ok().map(Argument::Double),
+        'b' => parse_hex(token.strip_prefix("0x")?).map(Argument::Binary),
+        't' => {
+            let (seconds, fraction) = token.split_once('.')?;
+            Some(Argument::Timetag(Timetag {
+                seconds: parse_hex_u32(seconds)?,
+                fraction: parse_hex_u32(fraction)?,
+            }))
+        },
+        'r' => match *parse_hex(token.strip_prefix('#')?)? {
+            [red, green, blue, alpha] => Some(Argument::Color(Rgba { red, green, blue, alpha })),
+            _ => None,
+        },
+        'm' => match *parse_hex(token.strip_prefix("0x")?)? {
+            [port, status, data1, data2] => {
+                Some(Argument::Midi(Midi { port, status, data1, data2 }))
+            },
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Parses pairs of hex digits into bytes.
+fn parse_hex(s: &str) -> Option<Vec<u8>> {
+    if s.len() % 2 != 0 || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    s.as_bytes()
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
+
+/// Parses up to 8 hex digits into a `u32`.
+fn parse_hex_u32(s: &str) -> Option<u32> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    u32::from_str_radix(s, 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::format;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    fn parse(text: &str) -> Result<Command, ParseError> {
+        text.parse()
+    }
+
+    fn round_trip(command: Command) {
+        let text = command.to_string();
+        assert_eq!(parse(&text), Ok(command), "{}", text);
+    }
+
+    #[test]
+    fn parses_string_escapes() {
+        let command = parse(r#"/a ,sc "say \"hi\"\\\n\r\t" '\''"#).unwrap();
+        assert_eq!(command.arguments, [
+            Argument::String("say \"hi\"\\\n\r\t".to_string()),
+            Argument::Char('\''),
+        ]);
+        assert_eq!(command.to_string(), r#"/a ,sc "say \"hi\"\\\n\r\t" '\''"#);
+        assert_eq!(parse(r#"/a ,s "\q""#), Err(ParseError::InvalidValue { offset: 6, tag: 's' }));
+        assert_eq!(parse(r#"/a ,s "x"y"#), Err(ParseError::InvalidValue { offset: 6, tag: 's' }));
+        assert_eq!(parse("/a ,c 'xy'"), Err(ParseError::InvalidValue { offset: 6, tag: 'c' }));
+    }
+
+    #[test]
+    fn parses_nested_arrays() {
+        let command = parse("/a ,i[s[f]]T 1 \"x\" 0.5").unwrap();
+        assert_eq!(command.arguments, [
+            Argument::Integer(1),
+            Argument::Array(vec![
+                Argument::String("x".to_string()),
+                Argument::Array(vec![Argument::Float(0.5)]),
+            ]),
+            Argument::Bool(true),
+        ]);
+        assert_eq!(parse("/a ,[]").unwrap().arguments, [Argument::Array(Vec::new())]);
+    }
+
+    #[test]
+    fn parses_arrays_nested_to_the_maximum_depth() {
+        let open = "[".repeat(MAX_ARRAY_DEPTH);
+        let close = "]".repeat(MAX_ARRAY_DEPTH);
+        let mut argument = parse(&format!("/a ,{}{}", open, close)).unwrap().arguments.remove(0);
+        for _ in 1..MAX_ARRAY_DEPTH {
+            argument = match argument {
+                Argument::Array(mut elements) => elements.remove(0),
+                argument => panic!("expected an array, got {:?}", argument),
+            };
+        }
+        assert_eq!(argument, Argument::Array(Vec::new()));
+    }
+
+    #[test]
+    fn rejects_arrays_nested_too_deep() {
+        let error = ParseError::TooDeep { offset: 4 + MAX_ARRAY_DEPTH };
+        assert_eq!(parse(&format!("/a ,{}", "[".repeat(MAX_ARRAY_DEPTH + 1))), Err(error.clone()));
+        assert_eq!(parse(&format!("/a ,{}", "[".repeat(200000))), Err(error));
+    }
+
+    #[test]
+    fn parses_hex_values() {
+        assert_eq!(parse("/a ,b 0x00ff10").unwrap().arguments,
+                   [Argument::Binary(vec![0x00, 0xff, 0x10])]);
+        assert_eq!(parse("/a ,b 0xABcd").unwrap().arguments, [Argument::Binary(vec![0xab, 0xcd])]);
+        assert_eq!(parse("/a ,b 0x").unwrap().arguments, [Argument::Binary(Vec::new())]);
+        assert_eq!(parse("/a ,r #ff8000ff").unwrap().arguments,
+                   [Argument::Color(Rgba { red: 0xff, green: 0x80, blue: 0, alpha: 0xff })]);
+        assert_eq!(parse("/a ,t 1.ffffffff").unwrap().arguments,
+                   [Argument::Timetag(Timetag { seconds: 1, fraction: 0xffff_ffff })]);
+
+        for &(text, tag) in &[("/a ,b 0x0", 'b'), ("/a ,b 0xzz", 'b'), ("/a ,b 00ff", 'b'),
+                              ("/a ,b 0x+1", 'b'), ("/a ,r #ff8000", 'r'),
+                              ("/a ,m 0x0090404000", 'm'), ("/a ,t 1", 't'),
+                              ("/a ,t 1.", 't'), ("/a ,t 100000000.0", 't')] {
+            assert_eq!(parse(text), Err(ParseError::InvalidValue { offset: 6, tag }), "{}", text);
+        }
+    }
+
+    #[test]
+    fn reports_each_error_with_its_offset() {
+        let cases = [
+            ("a ,i 1", ParseError::MissingAddress { offset: 0 }),
+            ("  ", ParseError::MissingAddress { offset: 2 }),
+            ("\"a\"", ParseError::MissingAddress { offset: 0 }),
+            ("\"/a", ParseError::UnterminatedQuote { offset: 0 }),
+            ("/a i 1", ParseError::MissingComma { offset: 3 }),
+            ("/a ,ix 1", ParseError::UnknownTag { offset: 5, tag: 'x' }),
+            ("/a ,i] 1", ParseError::UnbalancedArray { offset: 5 }),
+            ("/a ,i[i 1 2", ParseError::UnbalancedArray { offset: 5 }),
+            ("/a ,i", ParseError::MissingValue { offset: 5 }),
+            ("/a ,ii 1 ", ParseError::MissingValue { offset: 9 }),
+            ("/a ,i x", ParseError::InvalidValue { offset: 6, tag: 'i' }),
+            ("/a ,f 1.5.2", ParseError::InvalidValue { offset: 6, tag: 'f' }),
+            ("/a ,s \"abc", ParseError::UnterminatedQuote { offset: 6 }),
+            ("/a ,i 1 2", ParseError::TrailingText { offset: 8 }),
+            ("/a ,[[", ParseError::UnbalancedArray { offset: 5 }),
+        ];
+        for &(text, ref error) in &cases {
+            assert_eq!(parse(text).as_ref(), Err(error), "{}", text);
+            assert_eq!(error.offset(), parse(text).unwrap_err().offset());
+        }
+    }
+
+    #[test]
+    fn round_trips_every_type() {
+        round_trip(Command::new("/a"));
+        round_trip(Command::new("/a")
+            .arg(-42)
+            .arg(0.1f32)
+            .arg("say \"hi\"\n")
+            .arg(vec![0u8, 0xff])
+            .arg(Vec::<u8>::new())
+            .arg(-1i64 << 40)
+            .arg(Argument::Double(1e-300))
+            .arg(f32::INFINITY)
+            .arg(true)
+            .arg(false));
+        round_trip(Command {
+            address_pattern: "/a".to_string(),
+            arguments: vec![
+                Argument::Symbol("sym".to_string()),
+                Argument::Timetag(Timetag { seconds: 0xdead_beef, fraction: 1 }),
+                Argument::Char('\\'),
+                Argument::Char('é'),
+                Argument::Color(Rgba { red: 1, green: 2, blue: 3, alpha: 4 }),
+                Argument::Midi(Midi { port: 0, status: 0x90, data1: 0x40, data2: 0x7f }),
+                Argument::Nil,
+                Argument::Infinitum,
+                Argument::Array(vec![Argument::Integer(1), Argument::Array(Vec::new())]),
+            ],
+        });
+    }
+
+    #[test]
+    fn quotes_addresses_with_whitespace() {
+        let command = Command::new("/a b\t\"c\"").arg(1);
+        assert_eq!(command.to_string(), r#""/a b\t\"c\"" ,i 1"#);
+        round_trip(command);
+        round_trip(Command::new("/a\\b\"c"));
+        assert_eq!(parse("  \"/a\"  ").unwrap(), Command::new("/a"));
+    }
+}
@@ -0,0 +1,65 @@
+//! OSC time tags.
+
+#[cfg(feature = "std")]
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Seconds between the NTP epoch (1900-01-01) and the Unix epoch (1970-01-01).
+#[cfg(feature = "std")]
+const NTP_UNIX_OFFSET: u64 = 2_208_988_800;
+
+/// An OSC time tag.
+///
+/// Time tags are 64-bit NTP timestamps: the number of seconds since midnight on January 1, 1900,
+/// followed by a 32-bit fraction of a second.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timetag {
+    /// Whole seconds since the NTP epoch.
+    pub seconds: u32,
+    /// Fractions of a second, in units of 2^-32 seconds.
+    pub fraction: u32,
+}
+
+impl Timetag {
+
+    /// The special time tag meaning "immediately".
+    pub const IMMEDIATELY: Timetag = Timetag { seconds: 0, fraction: 1 };
+
+    /// Creates a time tag from its 64-bit wire representation.
+    pub fn from_bits(bits: u64) -> Timetag {
+        Timetag {
+            seconds: (bits >> 32) as u32,
+            fraction: bits as u32,
+        }
+    }
+
+    /// Returns the 64-bit wire representation of the time tag.
+    pub fn to_bits(self) -> u64 {
+        (u64::from(self.seconds) << 32) | u64::from(self.fraction)
+    }
+
+    /// Converts a system time to a time tag.
+    #[cfg(feature = "std")]
+    pub fn from_system_time(time: SystemTime) -> Timetag {
+        let since_ntp = match time.duration_since(UNIX_EPOCH) {
+            Ok(d) => Duration::from_secs(NTP_UNIX_OFFSET) + d,
+            Err(e) => {
+                Duration::from_secs(NTP_UNIX_OFFSET)
+                    .checked_sub(e.duration())
+                    .unwrap_or(Duration::from_secs(0))
+            },
+        };
+        Timetag {
+            seconds: since_ntp.as_secs() as u32,
+            fraction: ((u64::from(since_ntp.subsec_nanos()) << 32) / 1_000_000_000) as u32,
+        }
+    }
+
+    /// Converts the time tag to a system time.
+    #[cfg(feature = "std")]
+    pub fn to_system_time(self) -> SystemTime {
+        let nanos = (u64::from(self.fraction) * 1_000_000_000) >> 32;
+        let since_ntp = Duration::new(u64::from(self.seconds), nanos as u32);
+        let ntp_epoch = UNIX_EPOCH - Duration::from_secs(NTP_UNIX_OFFSET);
+        ntp_epoch + since_ntp
+    }
+}